@@ -0,0 +1,107 @@
+use crate::aliyun::challenge;
+use crate::config::Options;
+use crate::error::CommonError;
+use clap::ArgMatches;
+use fehler::{throw, throws};
+use std::env;
+use std::str::FromStr;
+use std::{thread, time::Duration};
+
+/// certbot --manual-auth-hook/--manual-cleanup-hook 传入的待验证域名
+const CERTBOT_DOMAIN: &str = "CERTBOT_DOMAIN";
+/// certbot --manual-auth-hook/--manual-cleanup-hook 传入的验证值
+const CERTBOT_VALIDATION: &str = "CERTBOT_VALIDATION";
+
+/// 验证记录操作
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChallengeAction {
+    /// 创建验证记录，certbot 为 auth，lego 为 present
+    Auth,
+    /// 删除验证记录
+    Cleanup,
+}
+
+impl FromStr for ChallengeAction {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auth" | "present" => Ok(ChallengeAction::Auth),
+            "cleanup" => Ok(ChallengeAction::Cleanup),
+            _ => Err("no match"),
+        }
+    }
+}
+
+/// ACME DNS-01 验证
+#[derive(Clone, Debug)]
+pub struct Challenge {
+    pub action: ChallengeAction,
+    /// 验证记录域名，如: _acme-challenge.example.com
+    pub name: String,
+    /// 验证值
+    pub value: String,
+    /// 创建验证记录后等待生效的时间(秒)
+    pub wait: u64,
+}
+
+impl Challenge {
+    /// 通过 acme 子命令参数构建
+    /// 指定域名与验证值时按 lego exec 的参数处理: present|cleanup <域名> <验证值>，
+    /// 否则读取 certbot 的环境变量 CERTBOT_DOMAIN、CERTBOT_VALIDATION
+    #[throws(CommonError)]
+    pub fn from_args(args: &ArgMatches) -> Self {
+        let action = args.value_of(super::OPTION_ACME_ACTION).unwrap_or_default();
+        let action: ChallengeAction = match action.parse() {
+            Ok(action) => action,
+            Err(_) => throw!(format!(
+                "不支持的验证操作{}，仅支持auth、present、cleanup",
+                action
+            )),
+        };
+        let wait = args.value_of(super::OPTION_ACME_WAIT).unwrap_or_default();
+        let wait = match wait.parse() {
+            Ok(wait) => wait,
+            Err(_) => throw!(format!("等待时间{}不合法，需为非负整数(秒)", wait)),
+        };
+        let (domain, value) = match (
+            args.value_of(super::OPTION_ACME_DOMAIN),
+            args.value_of(super::OPTION_ACME_VALUE),
+        ) {
+            (Some(domain), Some(value)) => (domain.to_string(), value.to_string()),
+            (None, None) => match (env::var(CERTBOT_DOMAIN), env::var(CERTBOT_VALIDATION)) {
+                (Ok(domain), Ok(value)) => (domain, value),
+                _ => throw!(format!(
+                    "缺少验证域名或验证值，需通过参数或环境变量{}、{}指定",
+                    CERTBOT_DOMAIN, CERTBOT_VALIDATION
+                )),
+            },
+            _ => throw!("验证域名与验证值需同时指定"),
+        };
+
+        Challenge {
+            action,
+            name: challenge::challenge_name(&domain),
+            value,
+            wait,
+        }
+    }
+
+    /// 创建或删除验证记录，失败时以非零状态码退出，便于 ACME 客户端中止验证
+    pub fn run(&self, options: &Options) {
+        let result = options.client().and_then(|client| match self.action {
+            ChallengeAction::Auth => {
+                challenge::present(&client, &self.name, &self.value, options.ttl)
+            }
+            ChallengeAction::Cleanup => challenge::cleanup(&client, &self.name, &self.value),
+        });
+        if let Err(e) = result {
+            log::error!("处理验证记录{}失败, error:{:?}", self.name, e);
+            std::process::exit(1);
+        }
+        if self.action == ChallengeAction::Auth && self.wait > 0 {
+            log::info!("等待验证记录生效: {}秒", self.wait);
+            thread::sleep(Duration::from_secs(self.wait));
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::aliyun::client::AliDnsClient;
+use crate::aliyun::domain;
+use crate::aliyun::record::{
+    AddDomainRecordRequest, DeleteDomainRecordRequest, DescribeSubDomainRecordsRequest, Record,
+};
+use crate::error::CommonError;
+use fehler::{throw, throws};
+
+/// ACME DNS-01 验证记录的主机记录前缀
+pub const CHALLENGE_PREFIX: &str = "_acme-challenge";
+/// 验证记录的解析类型
+const CHALLENGE_TYPE: &str = "TXT";
+/// 查询验证记录时每页数量(DescribeSubDomainRecords 最大为500)
+const CHALLENGE_PAGE_SIZE: i32 = 500;
+
+/// 待验证域名对应的验证记录域名，如: example.com -> _acme-challenge.example.com
+/// 已是验证记录域名时(如 lego 传入的 _acme-challenge.example.com.)仅去除末尾的 “.”
+pub fn challenge_name(domain: &str) -> String {
+    let domain = domain.trim().trim_end_matches('.').to_lowercase();
+    let domain = domain.trim_start_matches("*.");
+    if domain.starts_with(&format!("{}.", CHALLENGE_PREFIX)) {
+        domain.to_string()
+    } else {
+        format!("{}.{}", CHALLENGE_PREFIX, domain)
+    }
+}
+
+/// 从账号下的域名中查找完整域名所属的主域名
+/// @Return (主机记录, 主域名)
+#[throws(CommonError)]
+pub fn resolve_zone(client: &AliDnsClient, name: &str) -> (String, String) {
+    let zones: Vec<String> = domain::list(client)?
+        .into_iter()
+        .map(|d| d.domain_name)
+        .collect();
+    match select_zone(name, &zones) {
+        Some(zone) => zone,
+        None => throw!(format!("账号下没有域名{}所属的主域名", name)),
+    }
+}
+
+/// 在主域名列表中查找完整域名所属的主域名，存在多个时取最长的一个
+/// 如: _acme-challenge.www.example.com.cn -> (_acme-challenge.www, example.com.cn)
+/// @Return (主机记录, 主域名)
+fn select_zone(name: &str, zones: &[String]) -> Option<(String, String)> {
+    zones
+        .iter()
+        .map(|zone| zone.to_lowercase())
+        .filter(|zone| name.ends_with(&format!(".{}", zone)))
+        .max_by_key(|zone| zone.len())
+        .map(|zone| (name[..(name.len() - zone.len() - 1)].to_string(), zone))
+}
+
+/// 查询解析值为 value 的验证记录
+#[throws(CommonError)]
+fn find(client: &AliDnsClient, name: &str, zone: &str, value: &str) -> Vec<Record> {
+    let req = DescribeSubDomainRecordsRequest {
+        sub_domain: name.to_string(),
+        domain_name: Some(zone.to_string()),
+        page_size: Some(CHALLENGE_PAGE_SIZE),
+        kind: Some(CHALLENGE_TYPE.to_string()),
+        ..Default::default()
+    };
+    client
+        .describe_sub_domain_records(&req)?
+        .domain_records
+        .record
+        .into_iter()
+        .filter(|r| r.value.trim_matches('"') == value)
+        .collect()
+}
+
+/// 创建验证记录，解析值相同的记录已存在时略过
+/// name 为验证记录域名，如: _acme-challenge.example.com
+#[throws(CommonError)]
+pub fn present(client: &AliDnsClient, name: &str, value: &str, ttl: u32) {
+    let (rr, zone) = resolve_zone(client, name)?;
+    if !find(client, name, &zone, value)?.is_empty() {
+        log::info!("验证记录已存在，略过创建! 域名: {}, 解析值:{}", name, value);
+        return;
+    }
+    let req = AddDomainRecordRequest {
+        domain_name: zone,
+        rr,
+        kind: CHALLENGE_TYPE.to_string(),
+        value: value.to_string(),
+        ttl: Some(ttl),
+        ..Default::default()
+    };
+    let record_id = client.add_domain_record(&req)?.record_id;
+    log::info!(
+        "创建验证记录成功! 域名: {}, 解析值:{}, record_id:{}",
+        name,
+        value,
+        record_id
+    );
+}
+
+/// 删除解析值匹配的验证记录
+/// 同一域名的其他验证记录保留，如同时申请 example.com 与 *.example.com 的证书
+#[throws(CommonError)]
+pub fn cleanup(client: &AliDnsClient, name: &str, value: &str) {
+    let (_, zone) = resolve_zone(client, name)?;
+    let records = find(client, name, &zone, value)?;
+    if records.is_empty() {
+        log::info!("验证记录不存在，略过删除! 域名: {}, 解析值:{}", name, value);
+    }
+    for r in records {
+        client.delete_domain_record(&DeleteDomainRecordRequest {
+            record_id: r.record_id.clone(),
+        })?;
+        log::info!(
+            "删除验证记录成功! 域名: {}, 解析值:{}, record_id:{}",
+            name,
+            value,
+            r.record_id
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge_name_adds_prefix() {
+        assert_eq!(challenge_name("example.com"), "_acme-challenge.example.com");
+        assert_eq!(
+            challenge_name("www.Example.com"),
+            "_acme-challenge.www.example.com"
+        );
+    }
+
+    #[test]
+    fn challenge_name_strips_wildcard_and_trailing_dot() {
+        assert_eq!(
+            challenge_name("*.example.com"),
+            "_acme-challenge.example.com"
+        );
+        assert_eq!(
+            challenge_name(" example.com. "),
+            "_acme-challenge.example.com"
+        );
+    }
+
+    #[test]
+    fn challenge_name_keeps_existing_prefix() {
+        // lego 传入的是验证记录域名
+        assert_eq!(
+            challenge_name("_acme-challenge.example.com."),
+            "_acme-challenge.example.com"
+        );
+    }
+
+    #[test]
+    fn select_zone_prefers_longest_suffix() {
+        let zones: Vec<String> = ["com.cn", "example.com", "Example.com.cn"]
+            .iter()
+            .map(|z| z.to_string())
+            .collect();
+        assert_eq!(
+            select_zone("_acme-challenge.www.example.com.cn", &zones),
+            Some((
+                "_acme-challenge.www".to_string(),
+                "example.com.cn".to_string()
+            ))
+        );
+        assert_eq!(
+            select_zone("_acme-challenge.example.com", &zones),
+            Some(("_acme-challenge".to_string(), "example.com".to_string()))
+        );
+    }
+
+    #[test]
+    fn select_zone_requires_label_boundary() {
+        let zones = vec!["example.com".to_string()];
+        assert_eq!(select_zone("_acme-challenge.myexample.com", &zones), None);
+        assert_eq!(select_zone("_acme-challenge.example.org", &zones), None);
+    }
+}
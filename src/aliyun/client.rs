@@ -0,0 +1,338 @@
+use crate::aliyun::common::{request, CommonResponse, Endpoint, ErrorResponse};
+use crate::aliyun::credential::CredentialProvider;
+use crate::aliyun::domain::{DescribeDomainsRequest, Domain, DomainListResponse};
+use crate::aliyun::record::{
+    AddDomainRecordRequest, AddDomainRecordResponse, DeleteDomainRecordRequest,
+    DeleteDomainRecordResponse, DescribeDomainRecordsRequest, DescribeSubDomainRecordsRequest,
+    Record, RecordsResponse, SetDNSSLBStatusRequest, SetDNSSLBStatusResponse,
+    SetDomainRecordStatusRequest, SetDomainRecordStatusResponse, UpdateDNSSLBWeightRequest,
+    UpdateDNSSLBWeightResponse, UpdateDomainRecordRemarkRequest, UpdateDomainRecordRemarkResponse,
+    UpdateDomainRecordRequest, UpdateDomainRecordResponse,
+};
+use crate::error::CommonError;
+use fehler::{throw, throws};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+/// 分页查询默认每页数量(DescribeDomains 最大为100)
+pub const PAGE_SIZE: i32 = 100;
+
+/// 阿里云DNS客户端错误
+#[derive(Debug)]
+pub enum AliyunError {
+    /// 阿里云API返回的错误信息
+    Api(ErrorResponse),
+    /// 凭证、签名或网络请求错误
+    Request(CommonError),
+}
+
+impl fmt::Display for AliyunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AliyunError::Api(err) => write!(
+                f,
+                "阿里云API错误: {}, {}, RequestId: {}",
+                err.code, err.message, err.request_id
+            ),
+            AliyunError::Request(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for AliyunError {}
+
+impl From<CommonError> for AliyunError {
+    fn from(err: CommonError) -> Self {
+        AliyunError::Request(err)
+    }
+}
+
+/// 阿里云DNS客户端，持有凭证、接入配置与HTTP客户端
+/// 所有方法均为同步阻塞调用，请求完成后返回；在异步程序中使用时建议通过 spawn_blocking 等方式调用，
+/// 避免阻塞运行时的工作线程
+pub struct AliDnsClient {
+    endpoint: Endpoint,
+    provider: Box<dyn CredentialProvider>,
+}
+
+impl AliDnsClient {
+    pub fn new(endpoint: Endpoint, provider: Box<dyn CredentialProvider>) -> Self {
+        AliDnsClient { endpoint, provider }
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// 获取域名列表
+    #[throws(AliyunError)]
+    pub fn describe_domains(&self, req: &DescribeDomainsRequest) -> DomainListResponse {
+        self.call("DescribeDomains", req)?
+    }
+
+    /// 遍历所有域名，按需逐页请求；req 中的 page_number 作为起始页
+    pub fn domains(&self, req: &DescribeDomainsRequest) -> Paginator<'_, Domain> {
+        let mut req = req.clone();
+        let page_size = *req.page_size.get_or_insert(PAGE_SIZE);
+        let page_number = req.page_number.unwrap_or(1);
+        Paginator::new(page_number, page_size, move |page| {
+            req.page_number = Some(page);
+            let resp = self.describe_domains(&req)?;
+            Ok((resp.domains.domain, resp.total_count))
+        })
+    }
+
+    /// 获取域名的解析记录列表
+    #[throws(AliyunError)]
+    pub fn describe_domain_records(&self, req: &DescribeDomainRecordsRequest) -> RecordsResponse {
+        self.call("DescribeDomainRecords", req)?
+    }
+
+    /// 遍历域名下所有符合条件的解析记录，按需逐页请求；req 中的 page_number 作为起始页
+    pub fn domain_records(&self, req: &DescribeDomainRecordsRequest) -> Paginator<'_, Record> {
+        let mut req = req.clone();
+        let page_size = *req.page_size.get_or_insert(PAGE_SIZE);
+        let page_number = req.page_number.unwrap_or(1);
+        Paginator::new(page_number, page_size, move |page| {
+            req.page_number = Some(page);
+            let resp = self.describe_domain_records(&req)?;
+            Ok((resp.domain_records.record, resp.total_count))
+        })
+    }
+
+    /// 获取子域名的解析记录列表
+    #[throws(AliyunError)]
+    pub fn describe_sub_domain_records(
+        &self,
+        req: &DescribeSubDomainRecordsRequest,
+    ) -> RecordsResponse {
+        self.call("DescribeSubDomainRecords", req)?
+    }
+
+    /// 添加解析记录
+    #[throws(AliyunError)]
+    pub fn add_domain_record(&self, req: &AddDomainRecordRequest) -> AddDomainRecordResponse {
+        self.call("AddDomainRecord", req)?
+    }
+
+    /// 修改解析记录
+    #[throws(AliyunError)]
+    pub fn update_domain_record(
+        &self,
+        req: &UpdateDomainRecordRequest,
+    ) -> UpdateDomainRecordResponse {
+        self.call("UpdateDomainRecord", req)?
+    }
+
+    /// 删除解析记录
+    #[throws(AliyunError)]
+    pub fn delete_domain_record(
+        &self,
+        req: &DeleteDomainRecordRequest,
+    ) -> DeleteDomainRecordResponse {
+        self.call("DeleteDomainRecord", req)?
+    }
+
+    /// 设置解析记录状态
+    #[throws(AliyunError)]
+    pub fn set_domain_record_status(
+        &self,
+        req: &SetDomainRecordStatusRequest,
+    ) -> SetDomainRecordStatusResponse {
+        self.call("SetDomainRecordStatus", req)?
+    }
+
+    /// 修改解析记录的备注
+    #[throws(AliyunError)]
+    pub fn update_domain_record_remark(
+        &self,
+        req: &UpdateDomainRecordRemarkRequest,
+    ) -> UpdateDomainRecordRemarkResponse {
+        self.call("UpdateDomainRecordRemark", req)?
+    }
+
+    /// 开启或关闭子域名的权重配置
+    #[throws(AliyunError)]
+    pub fn set_dns_slb_status(&self, req: &SetDNSSLBStatusRequest) -> SetDNSSLBStatusResponse {
+        self.call("SetDNSSLBStatus", req)?
+    }
+
+    /// 修改解析记录的权重
+    #[throws(AliyunError)]
+    pub fn update_dns_slb_weight(
+        &self,
+        req: &UpdateDNSSLBWeightRequest,
+    ) -> UpdateDNSSLBWeightResponse {
+        self.call("UpdateDNSSLBWeight", req)?
+    }
+
+    /// 执行请求，将阿里云返回的错误信息转换为AliyunError::Api
+    #[throws(AliyunError)]
+    fn call<P, T>(&self, action: &str, req: &P) -> T
+    where
+        P: Serialize,
+        T: DeserializeOwned + fmt::Debug,
+    {
+        let mut params = to_params(req)?;
+        params.insert("Action".to_string(), action.to_string());
+        let resp = request::<CommonResponse<T, ErrorResponse>>(
+            &self.endpoint,
+            self.provider.as_ref(),
+            params,
+        )?;
+        match resp {
+            CommonResponse::Ok(data) => data,
+            CommonResponse::Err(err) => throw!(AliyunError::Api(err)),
+        }
+    }
+}
+
+/// 请求指定页，返回该页数据与总数
+type PageFetcher<'a, T> = Box<dyn FnMut(i32) -> Result<(Vec<T>, i32), AliyunError> + 'a>;
+
+/// 分页结果迭代器，当前页消费完后才请求下一页，直到达到 TotalCount 或返回空页。
+/// 请求出错时返回该错误并结束迭代
+pub struct Paginator<'a, T> {
+    fetch: PageFetcher<'a, T>,
+    page_number: i32,
+    page_size: i32,
+    buffer: VecDeque<T>,
+    done: bool,
+}
+
+impl<'a, T> Paginator<'a, T> {
+    fn new<F>(page_number: i32, page_size: i32, fetch: F) -> Self
+    where
+        F: FnMut(i32) -> Result<(Vec<T>, i32), AliyunError> + 'a,
+    {
+        Paginator {
+            fetch: Box::new(fetch),
+            page_number,
+            page_size,
+            buffer: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Paginator<'a, T> {
+    type Item = Result<T, AliyunError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() && !self.done {
+            match (self.fetch)(self.page_number) {
+                Ok((items, total_count)) => {
+                    let fetched = (self.page_number as i64) * (self.page_size as i64);
+                    if items.is_empty() || fetched >= total_count as i64 {
+                        self.done = true;
+                    }
+                    self.page_number += 1;
+                    self.buffer.extend(items);
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+}
+
+/// 将请求参数结构转换为阿里云API参数，忽略未设置的参数
+#[throws(CommonError)]
+fn to_params<P: Serialize>(req: &P) -> HashMap<String, String> {
+    let value = serde_json::to_value(req)?;
+    let fields = value.as_object().ok_or("请求参数格式错误")?;
+    fields
+        .iter()
+        .filter_map(|(k, v)| match v {
+            Value::Null => None,
+            Value::String(s) => Some((k.clone(), s.clone())),
+            other => Some((k.clone(), other.to_string())),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// 按页返回 pages 中的数据，并记录请求过的页码
+    fn paginator<'a>(
+        pages: Vec<Vec<i32>>,
+        total_count: i32,
+        requested: &'a RefCell<Vec<i32>>,
+    ) -> Paginator<'a, i32> {
+        Paginator::new(1, 2, move |page| {
+            requested.borrow_mut().push(page);
+            let items = pages.get((page - 1) as usize).cloned().unwrap_or_default();
+            Ok((items, total_count))
+        })
+    }
+
+    #[test]
+    fn errors_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<AliyunError>();
+        assert_send_sync::<CommonError>();
+    }
+
+    #[test]
+    fn paginator_stops_at_total_count() {
+        let requested = RefCell::new(Vec::new());
+        let pages = vec![vec![1, 2], vec![3, 4], vec![5, 6]];
+        let items: Vec<i32> = paginator(pages, 4, &requested)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(items, vec![1, 2, 3, 4]);
+        assert_eq!(*requested.borrow(), vec![1, 2]);
+    }
+
+    #[test]
+    fn paginator_stops_on_empty_page() {
+        let requested = RefCell::new(Vec::new());
+        // TotalCount 大于实际数量时，遇到空页结束
+        let pages = vec![vec![1, 2], vec![3]];
+        let items: Vec<i32> = paginator(pages, 100, &requested)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+        assert_eq!(*requested.borrow(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn paginator_is_lazy() {
+        let requested = RefCell::new(Vec::new());
+        let pages = vec![vec![1, 2], vec![3, 4]];
+        let first: Vec<i32> = paginator(pages, 4, &requested)
+            .take(2)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(first, vec![1, 2]);
+        assert_eq!(*requested.borrow(), vec![1]);
+    }
+
+    #[test]
+    fn paginator_ends_after_error() {
+        let mut calls = 0;
+        let mut pages = Paginator::new(1, 2, |page| {
+            calls += 1;
+            match page {
+                1 => Ok((vec![1, 2], 6)),
+                _ => Err(AliyunError::Request("请求失败".into())),
+            }
+        });
+        assert_eq!(pages.next().unwrap().unwrap(), 1);
+        assert_eq!(pages.next().unwrap().unwrap(), 2);
+        assert!(pages.next().unwrap().is_err());
+        assert!(pages.next().is_none());
+        drop(pages);
+        assert_eq!(calls, 2);
+    }
+}
@@ -1,20 +1,215 @@
-use crate::error::CommonError;
+use crate::aliyun::credential::{Credential, CredentialProvider};
+use crate::error::{self, CommonError};
+use crate::secret::Secret;
 use chrono::{SecondsFormat, Utc};
+use crypto::digest::Digest;
 use crypto::hmac::Hmac;
 use crypto::mac::Mac;
 use crypto::sha1::Sha1;
+use crypto::sha2::Sha256;
 use fehler::throws;
 use nanoid::nanoid;
-use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS, NON_ALPHANUMERIC};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::hash_map::HashMap;
 use std::collections::BTreeMap;
-use tokio::runtime::Runtime;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::runtime::{Handle, Runtime};
 use url::Url;
 
 /// 阿里云API URL
-const ALIYUN_API: &'static str = "https://alidns.aliyuncs.com/";
+pub const ALIYUN_API: &str = "https://alidns.aliyuncs.com/";
+/// 阿里云DNS API版本
+const ALIYUN_API_VERSION: &str = "2015-01-09";
+/// 阿里云STS API URL
+pub const STS_API: &str = "https://sts.aliyuncs.com/";
+/// 阿里云STS API版本
+pub const STS_API_VERSION: &str = "2015-04-01";
+/// V3签名算法
+const ACS3_HMAC_SHA256: &str = "ACS3-HMAC-SHA256";
+/// POST表单请求体类型
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+/// 默认每秒请求数上限
+pub const QPS: f64 = 10.0;
+/// 被限流后的最大重试次数
+const THROTTLING_RETRIES: u32 = 3;
+/// 被限流后首次重试的等待时间(毫秒)，之后每次翻倍
+const THROTTLING_BACKOFF_MILLIS: u64 = 1000;
+/// 被限流后速率下调的下限(每秒请求数)
+const THROTTLING_MIN_QPS: f64 = 0.5;
+
+/// 请求方式，POST时请求参数通过表单请求体传递
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HttpMethod {
+    Get,
+    Post,
+}
+
+impl HttpMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+        }
+    }
+}
+
+impl FromStr for HttpMethod {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "get" => Ok(HttpMethod::Get),
+            "post" => Ok(HttpMethod::Post),
+            _ => Err("no match"),
+        }
+    }
+}
+
+/// 签名版本
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SignatureVersion {
+    /// RPC签名(HMAC-SHA1)
+    V1,
+    /// V3签名(ACS3-HMAC-SHA256)
+    V3,
+}
+
+impl FromStr for SignatureVersion {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "v1" => Ok(SignatureVersion::V1),
+            "v3" => Ok(SignatureVersion::V3),
+            _ => Err("no match"),
+        }
+    }
+}
+
+/// 令牌桶限流器，同一接入点的所有请求共享
+/// 收到 Throttling 错误时速率减半，之后每次请求成功逐步恢复至配置的速率
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// 配置的每秒请求数，不大于0时不限流
+    qps: f64,
+    state: Mutex<RateState>,
+}
+
+#[derive(Debug)]
+struct RateState {
+    /// 当前每秒请求数
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(qps: f64) -> Self {
+        RateLimiter {
+            qps,
+            state: Mutex::new(RateState {
+                rate: qps,
+                tokens: qps.max(1.0),
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    /// 获取一个令牌，令牌不足时阻塞等待
+    pub fn acquire(&self) {
+        if self.qps <= 0.0 {
+            return;
+        }
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let elapsed = now.duration_since(state.last).as_secs_f64();
+                // 桶容量为1秒的请求数，允许短时突发
+                state.tokens = (state.tokens + elapsed * state.rate).min(state.rate.max(1.0));
+                state.last = now;
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - state.tokens) / state.rate)
+            };
+            thread::sleep(wait);
+        }
+    }
+
+    /// 被限流时降低速率并清空令牌
+    pub fn throttled(&self) {
+        if self.qps <= 0.0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.rate = (state.rate / 2.0).max(THROTTLING_MIN_QPS.min(self.qps));
+        state.tokens = 0.0;
+        log::warn!("阿里云API请求被限流，请求速率降低至{:.2}/s", state.rate);
+    }
+
+    /// 请求成功时逐步恢复速率
+    pub fn succeeded(&self) {
+        if self.qps <= 0.0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.rate < self.qps {
+            state.rate = (state.rate + self.qps / 10.0).min(self.qps);
+        }
+    }
+}
+
+/// 阿里云API接入配置: 接入点、地域、签名版本与请求方式
+#[derive(Clone, Debug)]
+pub struct Endpoint {
+    pub url: Url,
+    pub region_id: String,
+    pub signature_version: SignatureVersion,
+    /// 全局请求方式
+    pub method: HttpMethod,
+    /// 始终使用POST请求的Action
+    pub post_actions: Vec<String>,
+    pub client: reqwest::Client,
+    /// 请求限流器，克隆的接入点共享同一限流器
+    pub limiter: Arc<RateLimiter>,
+}
+
+impl Endpoint {
+    /// 接入点可省略协议，如: alidns.ap-southeast-1.aliyuncs.com
+    #[throws(CommonError)]
+    pub fn new(url: &str, region_id: &str) -> Self {
+        let url = if url.contains("://") {
+            Url::parse(url)?
+        } else {
+            Url::parse(&format!("https://{}", url))?
+        };
+        Endpoint {
+            url,
+            region_id: region_id.to_string(),
+            signature_version: SignatureVersion::V1,
+            method: HttpMethod::Get,
+            post_actions: Vec::new(),
+            client: reqwest::Client::new(),
+            limiter: Arc::new(RateLimiter::new(0.0)),
+        }
+    }
+
+    /// 获取Action对应的请求方式
+    pub fn method_of(&self, action: &str) -> HttpMethod {
+        if self.post_actions.iter().any(|a| a == action) {
+            HttpMethod::Post
+        } else {
+            self.method
+        }
+    }
+}
 
 /// 公共参数
 /// https://help.aliyun.com/document_detail/29745.html?spm=a2c4g.11186623.6.626.248a7ebbnmN79G
@@ -25,7 +220,7 @@ struct CommonParams {
     #[serde(rename(serialize = "Version"))]
     version: String,
     #[serde(rename(serialize = "AccessKeyId"))]
-    access_key_id: String,
+    access_key_id: Secret,
     #[serde(rename(serialize = "Signature"))]
     signature: String,
     #[serde(rename(serialize = "SignatureMethod"))]
@@ -36,19 +231,25 @@ struct CommonParams {
     signature_version: String,
     #[serde(rename(serialize = "SignatureNonce"))]
     signature_nonce: String,
+    #[serde(
+        rename(serialize = "SecurityToken"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    security_token: Option<Secret>,
 }
 
 impl Default for CommonParams {
     fn default() -> Self {
         let mut item = CommonParams {
             format: "JSON".to_string(),
-            version: "2015-01-09".to_string(),
-            access_key_id: "".to_string(),
+            version: ALIYUN_API_VERSION.to_string(),
+            access_key_id: Secret::from(""),
             signature: "".to_string(),
             signature_method: "HMAC-SHA1".to_string(),
             timestamp: "".to_string(),
             signature_version: "1.0".to_string(),
             signature_nonce: "".to_string(),
+            security_token: None,
         };
         item.signature_nonce();
         item.timestamp();
@@ -65,9 +266,17 @@ impl CommonParams {
         self.timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
     }
 
-    pub fn access_key_id(&mut self, ak_id: String) {
+    pub fn version(&mut self, version: &str) {
+        self.version = version.to_string()
+    }
+
+    pub fn access_key_id(&mut self, ak_id: Secret) {
         self.access_key_id = ak_id
     }
+
+    pub fn security_token(&mut self, token: Option<Secret>) {
+        self.security_token = token
+    }
 }
 
 pub const SETS: &AsciiSet = &NON_ALPHANUMERIC
@@ -75,39 +284,159 @@ pub const SETS: &AsciiSet = &NON_ALPHANUMERIC
     .remove(b'_')
     .remove(b'.')
     .remove(b'~');
-pub const SETS2: &AsciiSet = &CONTROLS.add(b'=');
 
-/// 执行阿里云请求
+/// 执行阿里云DNS请求
 #[throws(CommonError)]
-pub fn request<T>(ak_id: &str, ak_secret: &str, params: HashMap<String, String>) -> T
+pub fn request<T>(
+    endpoint: &Endpoint,
+    provider: &dyn CredentialProvider,
+    mut params: HashMap<String, String>,
+) -> T
 where
     T: DeserializeOwned + std::fmt::Debug,
 {
-    let req_method = "GET".to_string();
-    let (sign_str, params) = sign(req_method, ak_secret.to_string(), ak_id.to_string(), params)?;
+    params.insert("RegionId".to_string(), endpoint.region_id.clone());
+    request_api::<T>(endpoint, ALIYUN_API_VERSION, provider, params)?
+}
 
-    let mut url = Url::parse(ALIYUN_API).unwrap();
-    url.query_pairs_mut()
-        .append_pair("Signature", sign_str.as_str());
-    for (k, v) in params.iter() {
-        url.query_pairs_mut().append_pair(k, v);
+/// 执行指定阿里云产品的RPC请求
+/// 请求前经过限流器；返回 Throttling 错误时退避后重试
+#[throws(CommonError)]
+pub fn request_api<T>(
+    endpoint: &Endpoint,
+    version: &str,
+    provider: &dyn CredentialProvider,
+    params: HashMap<String, String>,
+) -> T
+where
+    T: DeserializeOwned + std::fmt::Debug,
+{
+    let action = params.get("Action").ok_or("缺少Action参数")?.clone();
+    let credential = provider.credential()?;
+    let mut retries = 0;
+    loop {
+        endpoint.limiter.acquire();
+        // 每次重试重新生成时间戳、随机数与签名
+        let builder = build_request(endpoint, version, &credential, params.clone())?;
+        let data = execute::<serde_json::Value>(builder)?;
+        if !is_throttling(&data) {
+            endpoint.limiter.succeeded();
+            break serde_json::from_value::<T>(data)?;
+        }
+
+        endpoint.limiter.throttled();
+        if retries >= THROTTLING_RETRIES {
+            log::error!("{}请求被限流，已重试{}次", action, retries);
+            break serde_json::from_value::<T>(data)?;
+        }
+        let backoff = Duration::from_millis(THROTTLING_BACKOFF_MILLIS << retries);
+        retries += 1;
+        log::warn!("{}请求被限流，{:?}后第{}次重试", action, backoff, retries);
+        thread::sleep(backoff);
     }
+}
 
-    do_get::<T>(url)?
+/// 响应是否为限流错误，如: Throttling、Throttling.User、Throttling.Api
+fn is_throttling(data: &serde_json::Value) -> bool {
+    data.get("Code")
+        .and_then(|code| code.as_str())
+        .is_some_and(|code| code.starts_with("Throttling"))
+}
+
+/// 签名并构建请求
+#[throws(CommonError)]
+fn build_request(
+    endpoint: &Endpoint,
+    version: &str,
+    credential: &Credential,
+    params: HashMap<String, String>,
+) -> reqwest::RequestBuilder {
+    let action = params.get("Action").ok_or("缺少Action参数")?;
+    let req_method = endpoint.method_of(action);
+    let client = &endpoint.client;
+    let mut url = endpoint.url.clone();
+    let builder = match endpoint.signature_version {
+        SignatureVersion::V1 => {
+            let (sign_str, mut params) =
+                sign(req_method.as_str().to_string(), version, credential, params)?;
+            params.insert("Signature".to_string(), sign_str);
+
+            // 请求参数与签名使用相同的规范化编码
+            let query = canonical_query(&params);
+            match req_method {
+                HttpMethod::Get => {
+                    url.set_query(Some(&query));
+                    client.get(url)
+                }
+                HttpMethod::Post => client
+                    .post(url)
+                    .header("content-type", FORM_CONTENT_TYPE)
+                    .body(query),
+            }
+        }
+        SignatureVersion::V3 => {
+            // V3签名中Action与Version通过请求头传递
+            let mut params: BTreeMap<String, String> = params.into_iter().collect();
+            let action = params.remove("Action").unwrap_or_default();
+            let mut headers = BTreeMap::new();
+            headers.insert("x-acs-action".to_string(), action);
+            headers.insert("x-acs-version".to_string(), version.to_string());
+            headers.insert(
+                "x-acs-date".to_string(),
+                Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            );
+            headers.insert("x-acs-signature-nonce".to_string(), nanoid!());
+
+            let host = match url.port() {
+                Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+                None => url.host_str().unwrap_or_default().to_string(),
+            };
+            // POST请求参数放在表单请求体中，查询字符串为空
+            let (query, body) = match req_method {
+                HttpMethod::Get => (params, String::new()),
+                HttpMethod::Post => {
+                    headers.insert("content-type".to_string(), FORM_CONTENT_TYPE.to_string());
+                    (BTreeMap::new(), canonical_query(&params))
+                }
+            };
+            let headers = sign_v3(
+                req_method.as_str(),
+                &host,
+                url.path(),
+                &query,
+                body.as_bytes(),
+                headers,
+                credential,
+            );
+
+            let query = canonical_query(&query);
+            url.set_query(if query.is_empty() { None } else { Some(&query) });
+            let mut builder = match req_method {
+                HttpMethod::Get => client.get(url),
+                HttpMethod::Post => client.post(url).body(body),
+            };
+            for (k, v) in headers.iter() {
+                builder = builder.header(k.as_str(), v.as_str());
+            }
+            builder
+        }
+    };
+    builder
 }
 
-// pub const SETS2: &AsciiSet = &SETS.remove(b'*');
 /// 签名，参考阿里云官方文档:
 /// https://help.aliyun.com/document_detail/29747.html?spm=a2c4g.11186623.2.14.2ea52b73jEErrO
 #[throws(CommonError)]
 pub fn sign(
     request_method: String,
-    ak_secret: String,
-    ak_id: String,
+    version: &str,
+    credential: &Credential,
     request_params: HashMap<String, String>,
 ) -> (String, BTreeMap<String, String>) {
     let mut common_params = CommonParams::default();
-    common_params.access_key_id(ak_id);
+    common_params.version(version);
+    common_params.access_key_id(credential.access_key_id.clone());
+    common_params.security_token(credential.security_token.clone());
     // 将公共参数转换成HashMap
     let json_value = serde_json::to_value(&common_params)?;
     let hm: HashMap<String, String> = serde_json::from_value(json_value)?;
@@ -120,38 +449,126 @@ pub fn sign(
         }
     }
 
-    // let mut encode = url::form_urlencoded::Serializer::new(String::new());
-    let mut str_sgin = String::new();
-    let mut index = 0;
-    for (k, v) in params.iter() {
-        if index == 0 {
-            str_sgin = format!(
-                "{}={}",
-                utf8_percent_encode(k, SETS).to_string(),
-                utf8_percent_encode(v, SETS).to_string()
-            );
-        } else {
-            str_sgin = format!(
-                "{}&{}={}",
-                str_sgin,
-                utf8_percent_encode(k, SETS).to_string(),
-                utf8_percent_encode(v, SETS).to_string()
-            );
-        }
-        index += 1;
+    let sign = rpc_signature(
+        &request_method,
+        &params,
+        credential.access_key_secret.expose(),
+    );
+    (sign, params)
+}
+
+/// RPC签名(HMAC-SHA1)
+/// StringToSign = HTTPMethod + "&" + percentEncode("/") + "&" + percentEncode(CanonicalizedQueryString)
+pub fn rpc_signature(
+    request_method: &str,
+    params: &BTreeMap<String, String>,
+    ak_secret: &str,
+) -> String {
+    let str_params = format!(
+        "{}&{}&{}",
+        request_method,
+        percent_encode("/"),
+        percent_encode(&canonical_query(params))
+    );
+    hmac(format!("{}&", ak_secret), str_params)
+}
+
+/// RFC 3986 编码: 仅保留 A-Z a-z 0-9 - _ . ~，空格编码为%20，非ASCII字符按UTF-8编码
+pub fn percent_encode(value: &str) -> String {
+    utf8_percent_encode(value, SETS).to_string()
+}
+
+/// RFC 3986 规范化查询字符串，按参数名排序
+pub fn canonical_query(params: &BTreeMap<String, String>) -> String {
+    params
+        .iter()
+        .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+/// V3签名(ACS3-HMAC-SHA256)，参考阿里云官方文档:
+/// https://help.aliyun.com/zh/sdk/product-overview/v3-request-structure-and-signature
+/// headers需包含x-acs-action、x-acs-version、x-acs-date、x-acs-signature-nonce，
+/// 返回补充host、x-acs-content-sha256、x-acs-security-token与Authorization后的全部请求头
+pub fn sign_v3(
+    request_method: &str,
+    host: &str,
+    path: &str,
+    params: &BTreeMap<String, String>,
+    body: &[u8],
+    mut headers: BTreeMap<String, String>,
+    credential: &Credential,
+) -> BTreeMap<String, String> {
+    let hashed_payload = sha256_hex(body);
+    headers.insert("host".to_string(), host.to_string());
+    headers.insert("x-acs-content-sha256".to_string(), hashed_payload.clone());
+    if let Some(token) = credential.security_token.as_ref() {
+        headers.insert(
+            "x-acs-security-token".to_string(),
+            token.expose().to_string(),
+        );
     }
-    // let mut str_sgin = encode.finish();
 
-    str_sgin = utf8_percent_encode(&str_sgin, SETS).to_string();
-    str_sgin = str_sgin.replace("%2E", ".");
-    str_sgin = str_sgin.replace("%2D", "-");
-    str_sgin = str_sgin.replace("%20", "+");
-    // str_sgin = str_sgin.replace("%2A", "*");
-    str_sgin = str_sgin.replace("~", "%7E");
+    // 参与签名的请求头: host、content-type 以及全部x-acs-前缀请求头(请求头名均为小写)
+    let signed: Vec<(&String, &str)> = headers
+        .iter()
+        .filter(|(k, _)| *k == "host" || *k == "content-type" || k.starts_with("x-acs-"))
+        .map(|(k, v)| (k, v.trim()))
+        .collect();
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(k, v)| format!("{}:{}\n", k, v))
+        .collect();
+    let signed_headers = signed
+        .iter()
+        .map(|(k, _)| k.as_str())
+        .collect::<Vec<&str>>()
+        .join(";");
 
-    let str_params = format!("{}&%2F&{}", request_method, str_sgin);
-    let sign = hmac(format!("{}&", ak_secret), str_params);
-    (sign, params)
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request_method,
+        if path.is_empty() { "/" } else { path },
+        canonical_query(params),
+        canonical_headers,
+        signed_headers,
+        hashed_payload
+    );
+    let str_sign = format!(
+        "{}\n{}",
+        ACS3_HMAC_SHA256,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let mut mac = Hmac::new(
+        Sha256::new(),
+        credential.access_key_secret.expose().as_bytes(),
+    );
+    mac.input(str_sign.as_bytes());
+    let signature = to_hex(mac.result().code());
+
+    headers.insert(
+        "Authorization".to_string(),
+        format!(
+            "{} Credential={},SignedHeaders={},Signature={}",
+            ACS3_HMAC_SHA256,
+            credential.access_key_id.expose(),
+            signed_headers,
+            signature
+        ),
+    );
+    headers
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(data);
+    hasher.result_str()
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
 fn hmac(key: String, data: String) -> String {
@@ -159,8 +576,7 @@ fn hmac(key: String, data: String) -> String {
     mac.input(data.as_bytes());
     let result = mac.result();
     let code = result.code();
-    // println!("{:02x?}", code);
-    format!("{}", base64::encode(code))
+    base64::encode(code)
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -173,33 +589,248 @@ pub enum CommonResponse<T, E> {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ErrorResponse {
     #[serde(rename(deserialize = "RequestId"))]
-    request_id: String,
-    #[serde(rename(deserialize = "Recommend"))]
-    recommend: String,
+    pub request_id: String,
+    #[serde(rename(deserialize = "Recommend"), default)]
+    pub recommend: String,
     #[serde(rename(deserialize = "Code"))]
-    code: String,
-    #[serde(rename(deserialize = "Message"))]
-    message: String,
-    #[serde(rename(deserialize = "HostId"))]
-    host_id: String,
+    pub code: String,
+    /// 已脱敏的错误信息
+    #[serde(
+        rename(deserialize = "Message"),
+        deserialize_with = "deserialize_redacted"
+    )]
+    pub message: String,
+    #[serde(rename(deserialize = "HostId"), default)]
+    pub host_id: String,
+}
+
+/// 反序列化错误信息时移除其中的签名与凭证
+fn deserialize_redacted<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    String::deserialize(deserializer).map(|message| error::redact_message(&message))
 }
 
 #[throws(CommonError)]
-async fn do_request<T>(url: Url) -> T
+async fn do_request<T>(request: reqwest::RequestBuilder) -> T
 where
     T: DeserializeOwned + std::fmt::Debug,
 {
-    reqwest::get(url).await?.json::<T>().await?
+    request
+        .send()
+        .await
+        .map_err(error::redact_request_error)?
+        .json::<T>()
+        .await
+        .map_err(error::redact_request_error)?
 }
 
 #[throws(CommonError)]
-pub fn do_get<T>(url: Url) -> T
+pub fn do_get<T>(client: &reqwest::Client, url: Url) -> T
 where
-    T: DeserializeOwned + std::fmt::Debug,
+    T: DeserializeOwned + std::fmt::Debug + Send,
 {
-    let resp: Result<T, CommonError> = Runtime::new().unwrap().block_on(async {
-        let data: T = do_request::<T>(url).await?;
-        Ok(data)
-    });
-    resp?
+    execute::<T>(client.get(url))?
+}
+
+/// 在独立的运行时中同步执行请求
+/// 调用方已处于 tokio 运行时中时(如在异步程序中使用 AliDnsClient)，改为在新线程中执行，避免嵌套运行时
+#[throws(CommonError)]
+fn execute<T>(request: reqwest::RequestBuilder) -> T
+where
+    T: DeserializeOwned + std::fmt::Debug + Send,
+{
+    let run =
+        move || -> Result<T, CommonError> { Runtime::new()?.block_on(do_request::<T>(request)) };
+    if Handle::try_current().is_err() {
+        return run()?;
+    }
+    thread::scope(|s| s.spawn(run).join()).map_err(|_| "执行请求的线程异常退出")??
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(limiter: &RateLimiter) -> f64 {
+        limiter.state.lock().unwrap().rate
+    }
+
+    /// 使用POST请求的接入点
+    fn post_endpoint(signature_version: SignatureVersion) -> Endpoint {
+        Endpoint {
+            signature_version,
+            method: HttpMethod::Post,
+            ..Endpoint::new("https://alidns.aliyuncs.com", "cn-hangzhou").unwrap()
+        }
+    }
+
+    /// 构建 DescribeDomainRecords 请求
+    fn build_post(signature_version: SignatureVersion) -> reqwest::Request {
+        let mut params = HashMap::new();
+        params.insert("Action".to_string(), "DescribeDomainRecords".to_string());
+        params.insert("DomainName".to_string(), "example.com".to_string());
+        let credential = Credential::new("id".into(), "secret".into());
+        build_request(
+            &post_endpoint(signature_version),
+            "2015-01-09",
+            &credential,
+            params,
+        )
+        .unwrap()
+        .build()
+        .unwrap()
+    }
+
+    fn header<'a>(req: &'a reqwest::Request, name: &str) -> &'a str {
+        req.headers().get(name).unwrap().to_str().unwrap()
+    }
+
+    fn body(req: &reqwest::Request) -> &str {
+        std::str::from_utf8(req.body().unwrap().as_bytes().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn execute_inside_tokio_runtime() {
+        // 已处于运行时中时不会因嵌套运行时而 panic，连接失败作为错误返回
+        let url = Url::parse("http://127.0.0.1:1/").unwrap();
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(async { do_get::<serde_json::Value>(&reqwest::Client::new(), url) });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn v1_post_sends_signed_form_body() {
+        let req = build_post(SignatureVersion::V1);
+        assert_eq!(req.method(), reqwest::Method::POST);
+        assert_eq!(req.url().query(), None);
+        assert_eq!(header(&req, "content-type"), FORM_CONTENT_TYPE);
+
+        let mut params: BTreeMap<String, String> =
+            url::form_urlencoded::parse(body(&req).as_bytes())
+                .into_owned()
+                .collect();
+        assert_eq!(params["Action"], "DescribeDomainRecords");
+        assert_eq!(params["DomainName"], "example.com");
+        // 请求体为规范化编码的参数，签名使用POST方法计算
+        assert_eq!(body(&req), canonical_query(&params));
+        let signature = params.remove("Signature").unwrap();
+        assert_eq!(signature, rpc_signature("POST", &params, "secret"));
+        assert_ne!(signature, rpc_signature("GET", &params, "secret"));
+    }
+
+    #[test]
+    fn v3_post_signs_form_body_with_empty_query() {
+        let req = build_post(SignatureVersion::V3);
+        assert_eq!(req.method(), reqwest::Method::POST);
+        assert_eq!(req.url().query(), None);
+        assert_eq!(header(&req, "content-type"), FORM_CONTENT_TYPE);
+        assert_eq!(header(&req, "x-acs-action"), "DescribeDomainRecords");
+        assert_eq!(body(&req), "DomainName=example.com");
+        assert_eq!(
+            header(&req, "x-acs-content-sha256"),
+            sha256_hex(body(&req).as_bytes())
+        );
+
+        // 使用相同的请求头、空查询字符串与请求体重新签名，结果一致
+        let headers: BTreeMap<String, String> = [
+            "content-type",
+            "x-acs-action",
+            "x-acs-version",
+            "x-acs-date",
+            "x-acs-signature-nonce",
+        ]
+        .iter()
+        .map(|name| (name.to_string(), header(&req, name).to_string()))
+        .collect();
+        let credential = Credential::new("id".into(), "secret".into());
+        let signed = sign_v3(
+            "POST",
+            "alidns.aliyuncs.com",
+            "/",
+            &BTreeMap::new(),
+            body(&req).as_bytes(),
+            headers,
+            &credential,
+        );
+        assert_eq!(header(&req, "authorization"), signed["Authorization"]);
+    }
+
+    #[test]
+    fn rate_limiter_halves_and_recovers() {
+        let limiter = RateLimiter::new(8.0);
+        limiter.throttled();
+        assert_eq!(rate(&limiter), 4.0);
+        limiter.throttled();
+        assert_eq!(rate(&limiter), 2.0);
+        // 每次成功恢复配置速率的 1/10，不超过配置的速率
+        limiter.succeeded();
+        assert!((rate(&limiter) - 2.8).abs() < 1e-9);
+        for _ in 0..20 {
+            limiter.succeeded();
+        }
+        assert_eq!(rate(&limiter), 8.0);
+    }
+
+    #[test]
+    fn rate_limiter_keeps_minimum_rate() {
+        let limiter = RateLimiter::new(2.0);
+        for _ in 0..10 {
+            limiter.throttled();
+        }
+        assert_eq!(rate(&limiter), THROTTLING_MIN_QPS);
+
+        // 配置的速率低于下限时以配置为准
+        let limiter = RateLimiter::new(0.2);
+        limiter.throttled();
+        assert_eq!(rate(&limiter), 0.2);
+    }
+
+    #[test]
+    fn rate_limiter_blocks_when_tokens_run_out() {
+        let limiter = RateLimiter::new(20.0);
+        let start = Instant::now();
+        // 桶内初始为1秒的请求数，允许突发
+        for _ in 0..20 {
+            limiter.acquire();
+        }
+        assert!(start.elapsed() < Duration::from_millis(40));
+        limiter.acquire();
+        limiter.acquire();
+        assert!(start.elapsed() >= Duration::from_millis(80));
+    }
+
+    #[test]
+    fn rate_limiter_throttled_clears_tokens() {
+        let limiter = RateLimiter::new(20.0);
+        limiter.throttled();
+        let start = Instant::now();
+        // 速率降低至10/s且令牌清空，下一个令牌需等待约100ms
+        limiter.acquire();
+        assert!(start.elapsed() >= Duration::from_millis(80));
+    }
+
+    #[test]
+    fn rate_limiter_unlimited() {
+        let limiter = RateLimiter::new(0.0);
+        limiter.throttled();
+        let start = Instant::now();
+        for _ in 0..1000 {
+            limiter.acquire();
+        }
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert_eq!(rate(&limiter), 0.0);
+    }
+
+    #[test]
+    fn error_response_message_is_redacted() {
+        let data = serde_json::json!({
+            "RequestId": "r",
+            "Code": "SignatureDoesNotMatch",
+            "Message": "server string to sign is:GET&%2F&AccessKeyId%3DLTAI_ID%26Action%3DAddDomainRecord",
+            "HostId": "h",
+        });
+        let err: ErrorResponse = serde_json::from_value(data).unwrap();
+        assert!(!err.message.contains("LTAI_ID"));
+        assert!(!format!("{:?}", err).contains("LTAI_ID"));
+    }
 }
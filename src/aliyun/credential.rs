@@ -0,0 +1,323 @@
+use crate::aliyun::common::{self, do_get, CommonResponse, Endpoint, ErrorResponse};
+use crate::error::CommonError;
+use crate::secret::Secret;
+use chrono::{DateTime, Duration, Utc};
+use fehler::{throw, throws};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use url::Url;
+
+/// ECS实例元数据服务默认地址
+pub const ECS_METADATA_URL: &str = "http://100.100.100.200";
+
+/// AssumeRole默认会话名称
+pub const ROLE_SESSION_NAME: &str = "aliyun-ddns";
+/// AssumeRole临时凭证默认有效期(秒)
+pub const ROLE_DURATION_SECS: u32 = 3600;
+
+/// 临时凭证提前刷新时间(秒)，避免请求途中凭证过期
+const REFRESH_AHEAD_SECS: i64 = 300;
+
+/// 阿里云访问凭证，使用STS临时凭证时需携带SecurityToken
+#[derive(Clone, Debug)]
+pub struct Credential {
+    pub access_key_id: Secret,
+    pub access_key_secret: Secret,
+    pub security_token: Option<Secret>,
+    pub expiration: Option<DateTime<Utc>>,
+}
+
+impl Credential {
+    pub fn new(access_key_id: Secret, access_key_secret: Secret) -> Self {
+        Credential {
+            access_key_id,
+            access_key_secret,
+            security_token: None,
+            expiration: None,
+        }
+    }
+
+    /// 是否即将过期，未设置过期时间的凭证视为长期有效
+    pub fn is_expiring(&self) -> bool {
+        match self.expiration {
+            Some(expiration) => expiration - Duration::seconds(REFRESH_AHEAD_SECS) <= Utc::now(),
+            None => false,
+        }
+    }
+}
+
+/// 凭证提供者，每次请求前获取当前有效的凭证
+pub trait CredentialProvider: Send + Sync {
+    fn credential(&self) -> Result<Credential, CommonError>;
+}
+
+/// 固定凭证(AccessKey 或 不刷新的STS凭证)
+impl CredentialProvider for Credential {
+    fn credential(&self) -> Result<Credential, CommonError> {
+        Ok(self.clone())
+    }
+}
+
+/// 可刷新的凭证提供者，缓存凭证直至即将过期后重新获取
+pub struct RefreshingProvider<F> {
+    fetch: F,
+    cached: Mutex<Option<Credential>>,
+}
+
+impl<F> RefreshingProvider<F>
+where
+    F: Fn() -> Result<Credential, CommonError>,
+{
+    pub fn new(fetch: F) -> Self {
+        RefreshingProvider {
+            fetch,
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+impl<F> CredentialProvider for RefreshingProvider<F>
+where
+    F: Fn() -> Result<Credential, CommonError> + Send + Sync,
+{
+    #[throws(CommonError)]
+    fn credential(&self) -> Credential {
+        let mut cached = self.cached.lock().unwrap();
+        if let Some(credential) = cached.as_ref() {
+            if !credential.is_expiring() {
+                return credential.clone();
+            }
+        }
+
+        let credential = (self.fetch)()?;
+        log::info!("获取临时凭证成功, 过期时间: {:?}", credential.expiration);
+        *cached = Some(credential.clone());
+        credential
+    }
+}
+
+/// STS临时凭证，ECS实例RAM角色与AssumeRole均返回该格式
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StsCredential {
+    #[serde(rename(deserialize = "AccessKeyId"))]
+    access_key_id: Secret,
+    #[serde(rename(deserialize = "AccessKeySecret"))]
+    access_key_secret: Secret,
+    #[serde(rename(deserialize = "SecurityToken"))]
+    security_token: Secret,
+    #[serde(rename(deserialize = "Expiration"))]
+    expiration: String,
+}
+
+impl StsCredential {
+    #[throws(CommonError)]
+    pub fn into_credential(self) -> Credential {
+        let expiration = DateTime::parse_from_rfc3339(&self.expiration)?;
+        Credential {
+            access_key_id: self.access_key_id,
+            access_key_secret: self.access_key_secret,
+            security_token: Some(self.security_token),
+            expiration: Some(expiration.with_timezone(&Utc)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct EcsRamRoleResponse {
+    #[serde(rename(deserialize = "Code"))]
+    code: String,
+    /// 获取失败时仅返回 Code
+    #[serde(flatten)]
+    credential: Option<StsCredential>,
+}
+
+/// 从ECS实例元数据服务获取RAM角色的临时凭证
+/// https://help.aliyun.com/document_detail/54579.html
+#[throws(CommonError)]
+pub fn fetch_ecs_ram_role(client: &reqwest::Client, metadata_url: &str, role: &str) -> Credential {
+    let url = Url::parse(&format!(
+        "{}/latest/meta-data/ram/security-credentials/{}",
+        metadata_url.trim_end_matches('/'),
+        role
+    ))?;
+    let resp = do_get::<EcsRamRoleResponse>(client, url)?;
+    match resp.credential {
+        Some(credential) if resp.code == "Success" => credential.into_credential()?,
+        _ => throw!(format!("获取ECS实例RAM角色{}凭证失败: {}", role, resp.code)),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct AssumeRoleResponse {
+    #[serde(rename(deserialize = "RequestId"))]
+    request_id: String,
+    #[serde(rename(deserialize = "Credentials"))]
+    credentials: StsCredential,
+}
+
+/// 使用基础凭证扮演RAM角色，获取该角色的临时凭证
+/// https://help.aliyun.com/document_detail/28763.html
+#[throws(CommonError)]
+pub fn assume_role(
+    endpoint: &Endpoint,
+    provider: &dyn CredentialProvider,
+    role_arn: &str,
+    session_name: &str,
+    duration: u32,
+) -> Credential {
+    let mut req_params = HashMap::new();
+    req_params.insert("Action".to_string(), "AssumeRole".to_string());
+    req_params.insert("RoleArn".to_string(), role_arn.to_string());
+    req_params.insert("RoleSessionName".to_string(), session_name.to_string());
+    req_params.insert("DurationSeconds".to_string(), duration.to_string());
+
+    let resp = common::request_api::<CommonResponse<AssumeRoleResponse, ErrorResponse>>(
+        endpoint,
+        common::STS_API_VERSION,
+        provider,
+        req_params,
+    )?;
+    match resp {
+        CommonResponse::Ok(data) => data.credentials.into_credential()?,
+        CommonResponse::Err(err) => throw!(format!("扮演RAM角色{}失败: {:?}", role_arn, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// 在本地端口模拟ECS实例元数据服务，响应一次请求
+    /// @Return (服务地址, 请求行)
+    fn serve_metadata(body: &'static str) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 4096];
+            let len = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..len]);
+            tx.send(request.lines().next().unwrap_or_default().to_string())
+                .unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        (url, rx)
+    }
+
+    fn credential(expires_in: Duration) -> Credential {
+        Credential {
+            security_token: Some("token".into()),
+            expiration: Some(Utc::now() + expires_in),
+            ..Credential::new("id".into(), "secret".into())
+        }
+    }
+
+    #[test]
+    fn refreshing_provider_caches_valid_credential() {
+        let fetched = AtomicUsize::new(0);
+        let provider = RefreshingProvider::new(|| {
+            fetched.fetch_add(1, Ordering::SeqCst);
+            Ok(credential(Duration::hours(1)))
+        });
+        provider.credential().unwrap();
+        provider.credential().unwrap();
+        assert_eq!(fetched.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn refreshing_provider_refreshes_before_expiry() {
+        // 剩余有效期小于 REFRESH_AHEAD_SECS 时每次都重新获取
+        let fetched = AtomicUsize::new(0);
+        let provider = RefreshingProvider::new(|| {
+            fetched.fetch_add(1, Ordering::SeqCst);
+            Ok(credential(Duration::seconds(REFRESH_AHEAD_SECS - 60)))
+        });
+        provider.credential().unwrap();
+        provider.credential().unwrap();
+        assert_eq!(fetched.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn refreshing_provider_refreshes_expired_credential() {
+        let fetched = AtomicUsize::new(0);
+        let provider = RefreshingProvider::new(|| {
+            let count = fetched.fetch_add(1, Ordering::SeqCst);
+            // 首次获取的凭证已过期，之后获取的凭证长期有效
+            if count == 0 {
+                Ok(credential(Duration::seconds(-10)))
+            } else {
+                Ok(credential(Duration::hours(1)))
+            }
+        });
+        for _ in 0..3 {
+            provider.credential().unwrap();
+        }
+        assert_eq!(fetched.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn refreshing_provider_does_not_cache_errors() {
+        let fetched = AtomicUsize::new(0);
+        let provider = RefreshingProvider::new(|| {
+            fetched.fetch_add(1, Ordering::SeqCst);
+            Err("fetch failed".into())
+        });
+        assert!(provider.credential().is_err());
+        assert!(provider.credential().is_err());
+        assert_eq!(fetched.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn long_term_credential_never_expires() {
+        let credential = Credential::new("id".into(), "secret".into());
+        assert!(!credential.is_expiring());
+    }
+
+    #[test]
+    fn fetch_ecs_ram_role_from_metadata_service() {
+        let (url, request) = serve_metadata(
+            r#"{
+                "AccessKeyId": "STS.id",
+                "AccessKeySecret": "secret",
+                "Expiration": "2030-01-01T00:00:00Z",
+                "SecurityToken": "token",
+                "LastUpdated": "2029-12-31T18:00:00Z",
+                "Code": "Success"
+            }"#,
+        );
+        let credential = fetch_ecs_ram_role(&reqwest::Client::new(), &url, "ddns-role").unwrap();
+        assert_eq!(
+            request.recv().unwrap(),
+            "GET /latest/meta-data/ram/security-credentials/ddns-role HTTP/1.1"
+        );
+        assert_eq!(credential.access_key_id.expose(), "STS.id");
+        assert_eq!(credential.access_key_secret.expose(), "secret");
+        assert_eq!(credential.security_token.unwrap().expose(), "token");
+        assert_eq!(
+            credential.expiration.unwrap().to_rfc3339(),
+            "2030-01-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn fetch_ecs_ram_role_reports_failure_code() {
+        let (url, _request) = serve_metadata(r#"{"Code": "Failed"}"#);
+        let error = fetch_ecs_ram_role(&reqwest::Client::new(), &url, "ddns-role")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("ddns-role"));
+        assert!(error.contains("Failed"));
+    }
+}
@@ -1,90 +1,98 @@
-use crate::aliyun::common::{request, CommonResponse, ErrorResponse};
-use crate::config::Options;
-use crate::error::CommonError;
+use crate::aliyun::client::{AliDnsClient, AliyunError};
 use fehler::throws;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+
+/// DescribeDomains 请求参数
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DescribeDomainsRequest {
+    #[serde(
+        rename(serialize = "PageNumber"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub page_number: Option<i32>,
+    #[serde(
+        rename(serialize = "PageSize"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub page_size: Option<i32>,
+    #[serde(rename(serialize = "KeyWord"), skip_serializing_if = "Option::is_none")]
+    pub key_word: Option<String>,
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DomainListResponse {
     #[serde(rename(deserialize = "RequestId"))]
-    request_id: String,
+    pub request_id: String,
     #[serde(rename(deserialize = "PageNumber"))]
-    page_number: i32,
+    pub page_number: i32,
     #[serde(rename(deserialize = "TotalCount"))]
-    total_count: i32,
+    pub total_count: i32,
     #[serde(rename(deserialize = "PageSize"))]
-    page_size: i32,
+    pub page_size: i32,
     #[serde(rename(deserialize = "Domains"))]
-    domains: DomainList,
+    pub domains: DomainList,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct DomainList {
+pub struct DomainList {
     #[serde(rename(deserialize = "Domain"))]
-    domain: Vec<Domain>,
+    pub domain: Vec<Domain>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct Domain {
-    #[serde(rename(deserialize = "AliDomain"))]
-    ali_domain: bool,
-    #[serde(rename(deserialize = "ResourceGroupId"))]
-    resource_group_id: String,
+pub struct Domain {
+    #[serde(rename(deserialize = "AliDomain"), default)]
+    pub ali_domain: bool,
+    #[serde(rename(deserialize = "ResourceGroupId"), default)]
+    pub resource_group_id: String,
     #[serde(rename(deserialize = "DomainName"))]
-    domain_name: String,
-    #[serde(rename(deserialize = "CreateTime"))]
-    create_time: String,
-    #[serde(rename(deserialize = "PunyCode"))]
-    puny_code: String,
-    #[serde(rename(deserialize = "DnsServers"))]
-    dns_servers: DnsServer,
-    #[serde(rename(deserialize = "Starmark"))]
-    star_mark: bool,
-    #[serde(rename(deserialize = "VersionCode"))]
-    version_code: String,
+    pub domain_name: String,
+    #[serde(rename(deserialize = "CreateTime"), default)]
+    pub create_time: String,
+    #[serde(rename(deserialize = "PunyCode"), default)]
+    pub puny_code: String,
+    #[serde(rename(deserialize = "DnsServers"), default)]
+    pub dns_servers: DnsServer,
+    #[serde(rename(deserialize = "Starmark"), default)]
+    pub star_mark: bool,
+    #[serde(rename(deserialize = "VersionCode"), default)]
+    pub version_code: String,
     #[serde(rename(deserialize = "DomainId"))]
-    domain_id: String,
-    #[serde(rename(deserialize = "VersionName"))]
-    version_name: String,
-    #[serde(rename(deserialize = "RecordCount"))]
-    record_count: i32,
-    #[serde(rename(deserialize = "CreateTimestamp"))]
-    create_timestamp: i64,
-    #[serde(rename(deserialize = "Tags"))]
-    tags: Tags,
+    pub domain_id: String,
+    #[serde(rename(deserialize = "VersionName"), default)]
+    pub version_name: String,
+    #[serde(rename(deserialize = "RecordCount"), default)]
+    pub record_count: i32,
+    #[serde(rename(deserialize = "CreateTimestamp"), default)]
+    pub create_timestamp: i64,
+    #[serde(rename(deserialize = "Tags"), default)]
+    pub tags: Tags,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Tags {
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Tags {
     #[serde(rename(deserialize = "Tag"))]
-    tag: Vec<String>,
+    pub tag: Vec<Tag>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct DnsServer {
-    #[serde(rename(deserialize = "DnsServer"))]
-    dns_server: Vec<String>,
+pub struct Tag {
+    #[serde(rename(deserialize = "Key"))]
+    pub key: String,
+    #[serde(rename(deserialize = "Value"))]
+    pub value: String,
 }
 
-/// 获取所有的域名
-#[throws(CommonError)]
-pub fn list(opt: &Options) -> CommonResponse<DomainListResponse, ErrorResponse> {
-    let ak_secret = opt.access_key_secret.clone().ok_or("缺少阿里云AK Secret")?;
-    let ak_id = opt.access_key_id.clone().ok_or("缺少阿里云AK ID")?;
-
-    let mut req_params = HashMap::new();
-    req_params.insert("Action".to_string(), "DescribeDomains".to_string());
-    req_params.insert("PageSize".to_string(), "100".to_string());
-    // let req_method = "GET".to_string();
-
-    // let (sign_str, params) = sign(req_method, ak_secret, ak_id, req_params)?;
-    // let mut url = Url::parse(aliyun::ALIYUN_API).unwrap();
-    // url.query_pairs_mut()
-    //     .append_pair("Signature", sign_str.as_str());
-    // for (k, v) in params.iter() {
-    //     url.query_pairs_mut().append_pair(k, v);
-    // }
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DnsServer {
+    #[serde(rename(deserialize = "DnsServer"))]
+    pub dns_server: Vec<String>,
+}
 
-    request::<CommonResponse<DomainListResponse, ErrorResponse>>(&ak_id, &ak_secret, req_params)?
+/// 获取所有的域名，自动翻页
+#[throws(AliyunError)]
+pub fn list(client: &AliDnsClient) -> Vec<Domain> {
+    client
+        .domains(&DescribeDomainsRequest::default())
+        .collect::<Result<Vec<_>, _>>()?
 }
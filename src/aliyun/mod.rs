@@ -1,8 +1,15 @@
+pub mod challenge;
+pub mod client;
 pub mod common;
+pub mod credential;
 pub mod domain;
 pub mod record;
+pub use client::{AliDnsClient, AliyunError, Paginator};
 pub use domain::list as list_domain;
-pub use record::create_records;
-pub use record::list as list_records;
+pub use record::plan as plan_record;
 pub use record::split as split_records;
-pub use record::update_records;
+pub use record::sync as sync_record;
+pub use record::{
+    Action, Change, DisabledPolicy, DuplicatePolicy, Plan, PlanFormat, RecordStatus, RecordType,
+    StalePolicy, StaticRecord, StaticType, SyncOptions, SyncOutcome, Target, TargetDefaults,
+};
@@ -1,274 +1,2149 @@
-use crate::aliyun::common::{request, CommonResponse, ErrorResponse};
+use crate::aliyun::client::{AliDnsClient, AliyunError};
 use crate::config::Options;
 use crate::error::CommonError;
-use crate::ip::{self, IPOption};
+use crate::ip::IPOption;
 use fehler::{throw, throws};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt;
 use std::net::IpAddr;
+use std::str::FromStr;
 
-/// IPV4 解析类型
-const RECORD_TYPE_A: &'static str = "A";
-/// IPV6 解析类型
-const RECORD_TYPE_AAAA: &'static str = "AAAA";
+/// 目标域名与解析类型的分隔符，如: www.example.com:A+AAAA
+const TARGET_TYPES_SEPARATOR: char = ':';
+/// 多个解析类型的分隔符
+const TYPES_SEPARATOR: char = '+';
+/// 默认解析线路
+pub const LINE_DEFAULT: &str = "default";
+/// 目标域名与参数的分隔符，如: www.example.com?stale=delete&line=telecom
+const TARGET_PARAMS_SEPARATOR: char = '?';
+/// 多个参数的分隔符
+const PARAMS_SEPARATOR: char = '&';
+/// 静态解析类型与解析值的分隔符，如: info.example.com:TXT=ip={ipv4}
+const STATIC_VALUE_SEPARATOR: char = '=';
+/// 主域名自身的主机记录
+const RR_APEX: &str = "@";
+/// 拉取主域名解析记录时每页数量(DescribeDomainRecords 最大为500)
+const RECORD_PAGE_SIZE: i32 = 500;
 
+/// 地址解析类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RecordType {
+    /// IPv4
+    A,
+    /// IPv6
+    AAAA,
+}
+
+impl RecordType {
+    pub const ALL: [RecordType; 2] = [RecordType::A, RecordType::AAAA];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordType::A => "A",
+            RecordType::AAAA => "AAAA",
+        }
+    }
+
+    /// 本地公网IP中对应类型的地址
+    pub fn ip_of(&self, ips: &IPOption) -> Option<IpAddr> {
+        match self {
+            RecordType::A => Some(IpAddr::V4(ips.ipv4())),
+            RecordType::AAAA => ips.ipv6().map(IpAddr::V6),
+        }
+    }
+}
+
+impl FromStr for RecordType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "A" => Ok(RecordType::A),
+            "AAAA" => Ok(RecordType::AAAA),
+            _ => Err(format!("不支持的解析类型{}，仅支持A、AAAA", s)),
+        }
+    }
+}
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 需要同步的域名
+#[derive(Clone, Debug)]
+pub struct Target {
+    /// 主机记录，如: www
+    pub rr: String,
+    /// 主域名，如: example.com
+    pub domain: String,
+    /// 需要维护的解析类型
+    pub types: Vec<RecordType>,
+    /// 未显式配置解析类型，同时维护域名下已存在的AAAA解析(与未支持配置解析类型的版本一致)
+    pub existing_types: bool,
+    /// 未获取到本地对应类型IP地址时的处理方式
+    pub stale: StalePolicy,
+    /// 解析线路，如: default、telecom、unicom、mobile、oversea
+    pub line: String,
+    /// 成员标识，设置后仅维护备注为该标识的解析记录，并通过权重与其他成员共同解析
+    pub member: Option<String>,
+    /// 成员模式下的解析权重(1-100)
+    pub weight: i32,
+    /// 配置中声明的静态或模板解析记录
+    pub records: Vec<StaticRecord>,
+}
+
+/// 域名未单独指定时使用的默认配置
+#[derive(Clone, Debug)]
+pub struct TargetDefaults {
+    /// 为 None 时维护A解析及已存在的AAAA解析
+    pub types: Option<Vec<RecordType>>,
+    pub stale: StalePolicy,
+    pub line: String,
+    pub member: Option<String>,
+    pub weight: i32,
+}
+
+impl Target {
+    /// 解析域名配置，格式: 域名[:类型+类型][?参数=值&参数=值]
+    /// 如: v6.example.com:AAAA?stale=delete&line=telecom&member=node1&weight=10，
+    /// 未指定的配置使用 defaults
+    #[throws(CommonError)]
+    pub fn parse(spec: &str, defaults: &TargetDefaults) -> Self {
+        let mut parts = spec.splitn(2, TARGET_PARAMS_SEPARATOR);
+        let head = parts.next().unwrap_or_default();
+        let params = parts.next();
+
+        let mut parts = head.splitn(2, TARGET_TYPES_SEPARATOR);
+        let name = parts.next().unwrap_or_default().trim();
+        let (types, existing_types) = match (parts.next(), defaults.types.as_ref()) {
+            (Some(var), _) => (parse_types(var, TYPES_SEPARATOR)?, false),
+            (None, Some(types)) => (types.clone(), false),
+            (None, None) => (vec![RecordType::A], true),
+        };
+
+        let (rr, domain) = split_name(name, false)?;
+        let mut target = Target {
+            rr,
+            domain,
+            types,
+            existing_types,
+            stale: defaults.stale,
+            line: defaults.line.clone(),
+            member: defaults.member.clone(),
+            weight: defaults.weight,
+            records: Vec::new(),
+        };
+
+        let params = params.unwrap_or_default().split(PARAMS_SEPARATOR);
+        for param in params.filter(|p| !p.trim().is_empty()) {
+            let mut kv = param.splitn(2, '=');
+            let key = kv.next().unwrap_or_default().trim();
+            let value = kv.next().unwrap_or_default().trim();
+            match key {
+                "stale" => {
+                    target.stale = value
+                        .parse()
+                        .map_err(|_| format!("域名{}参数stale的值{}不合法", name, value))?
+                }
+                "line" if !value.is_empty() => target.line = value.to_string(),
+                "member" if !value.is_empty() => target.member = Some(value.to_string()),
+                "weight" => target.weight = parse_weight(value)?,
+                _ => throw!(format!("域名{}不支持参数{}", name, key)),
+            }
+        }
+        target
+    }
+
+    /// 仅维护静态解析记录的域名，使用默认配置，不维护地址解析
+    #[throws(CommonError)]
+    pub fn of_static(name: &str, defaults: &TargetDefaults) -> Self {
+        let (rr, domain) = split_name(name, true)?;
+        Target {
+            rr,
+            domain,
+            types: Vec::new(),
+            existing_types: false,
+            stale: defaults.stale,
+            line: defaults.line.clone(),
+            member: defaults.member.clone(),
+            weight: defaults.weight,
+            records: Vec::new(),
+        }
+    }
+
+    /// 完整域名
+    pub fn name(&self) -> String {
+        if self.rr == RR_APEX {
+            self.domain.clone()
+        } else {
+            format!("{}.{}", self.rr, self.domain)
+        }
+    }
+}
+
+/// 拆分完整域名为主机记录与主域名，如: www.example.com -> (www, example.com)
+/// allow_apex 为 true 时允许主域名自身，主机记录为 @
+#[throws(CommonError)]
+fn split_name(name: &str, allow_apex: bool) -> (String, String) {
+    let name = name.trim().trim_end_matches('.');
+    let segments: Vec<&str> = name.split('.').collect();
+    let l = segments.len();
+    if l == 2 && allow_apex && segments.iter().all(|s| !s.is_empty()) {
+        return (RR_APEX.to_string(), name.to_string());
+    }
+    if l <= 2 {
+        throw!(format!("域名{}不合法，必须使用二级或以下域名", name))
+    }
+    (
+        segments[0..(l - 2)].join("."),
+        segments[(l - 2)..l].join("."),
+    )
+}
+
+/// 静态或模板解析记录的类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StaticType {
+    CNAME,
+    TXT,
+    MX,
+    SRV,
+}
+
+impl StaticType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StaticType::CNAME => "CNAME",
+            StaticType::TXT => "TXT",
+            StaticType::MX => "MX",
+            StaticType::SRV => "SRV",
+        }
+    }
+
+    /// 比对前规范化解析值: TXT 忽略两端的引号，其他类型忽略域名的大小写及末尾的 “.”
+    fn normalize(&self, value: &str) -> String {
+        match self {
+            StaticType::TXT => value.trim_matches('"').to_string(),
+            _ => value.trim_end_matches('.').to_lowercase(),
+        }
+    }
+}
+
+impl FromStr for StaticType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "CNAME" => Ok(StaticType::CNAME),
+            "TXT" => Ok(StaticType::TXT),
+            "MX" => Ok(StaticType::MX),
+            "SRV" => Ok(StaticType::SRV),
+            _ => Err(format!("不支持的解析类型{}，仅支持CNAME、TXT、MX、SRV", s)),
+        }
+    }
+}
+
+impl fmt::Display for StaticType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 配置中声明的静态或模板解析记录，每轮同步时渲染解析值并与阿里云上的记录比对
+/// 解析值模板支持: {ipv4}、{ipv6} 本地公网IP地址，{timestamp} 本轮同步的Unix时间戳(秒)
+#[derive(Clone, Debug)]
+pub struct StaticRecord {
+    pub kind: StaticType,
+    /// 解析值模板，MX 解析不包含优先级
+    pub template: String,
+    /// MX 解析的优先级
+    pub priority: Option<i32>,
+}
+
+impl StaticRecord {
+    /// 解析静态解析记录配置，格式: 域名:类型=解析值
+    /// 如: api.example.com:CNAME=www.example.com、example.com:MX=10 mail.example.com、
+    /// _sip._tcp.example.com:SRV=0 5 5060 sip.example.com、info.example.com:TXT=ip={ipv4}
+    /// @Return (域名, 解析记录)
+    #[throws(CommonError)]
+    pub fn parse(spec: &str) -> (String, Self) {
+        let mut parts = spec.splitn(2, STATIC_VALUE_SEPARATOR);
+        let head = parts.next().unwrap_or_default();
+        let template = match parts.next() {
+            Some(var) if !var.trim().is_empty() => var.trim(),
+            _ => throw!(format!("解析记录{}缺少解析值", spec)),
+        };
+        let mut parts = head.splitn(2, TARGET_TYPES_SEPARATOR);
+        let name = parts.next().unwrap_or_default().trim();
+        let kind: StaticType = match parts.next() {
+            Some(var) => var.parse()?,
+            None => throw!(format!("解析记录{}缺少解析类型", spec)),
+        };
+
+        let mut record = StaticRecord {
+            kind,
+            template: template.to_string(),
+            priority: None,
+        };
+        match kind {
+            StaticType::CNAME => record.template = template.trim_end_matches('.').to_string(),
+            StaticType::MX => {
+                let mut fields = template.split_whitespace();
+                let priority = fields.next().and_then(|p| p.parse().ok());
+                match (priority, fields.next(), fields.next()) {
+                    (Some(priority), Some(host), None) => {
+                        record.priority = Some(priority);
+                        record.template = host.trim_end_matches('.').to_string();
+                    }
+                    _ => throw!(format!(
+                        "MX解析值{}不合法，格式为: 优先级 邮件服务器",
+                        template
+                    )),
+                }
+            }
+            StaticType::SRV if template.split_whitespace().count() != 4 => throw!(format!(
+                "SRV解析值{}不合法，格式为: 优先级 权重 端口 目标地址",
+                template
+            )),
+            _ => {}
+        }
+        (name.to_string(), record)
+    }
+
+    /// 渲染解析值模板
+    /// @Return 模板引用的IP地址不存在时为 None
+    pub fn render(&self, ips: &IPOption, timestamp: u64) -> Option<String> {
+        let mut value = self
+            .template
+            .replace("{ipv4}", &ips.ipv4().to_string())
+            .replace("{timestamp}", &timestamp.to_string());
+        if value.contains("{ipv6}") {
+            value = value.replace("{ipv6}", &ips.ipv6()?.to_string());
+        }
+        Some(value)
+    }
+
+    /// 已有的解析值是否可能由该模板渲染得到，用于在同名同类型的多条记录中识别该配置对应的记录
+    /// MX 解析仅比较邮件服务器，优先级不同时更新
+    pub fn matches(&self, value: &str) -> bool {
+        match_template(
+            &self.kind.normalize(&self.template),
+            &self.kind.normalize(value),
+        )
+    }
+}
+
+/// 占位符渲染结果可能包含的字符
+type CharClass = fn(char) -> bool;
+
+/// 解析值模板的占位符
+const TEMPLATE_PLACEHOLDERS: [(&str, CharClass); 3] = [
+    ("{ipv4}", |c| c.is_ascii_digit() || c == '.'),
+    ("{ipv6}", |c| c.is_ascii_hexdigit() || c == ':' || c == '.'),
+    ("{timestamp}", |c| c.is_ascii_digit()),
+];
+
+/// 按模板匹配解析值，占位符匹配任意长度的对应字符，其余部分需完全相同
+fn match_template(template: &str, value: &str) -> bool {
+    for (placeholder, allowed) in TEMPLATE_PLACEHOLDERS.iter() {
+        if let Some(rest) = template.strip_prefix(placeholder) {
+            let len = value.find(|c| !allowed(c)).unwrap_or(value.len());
+            return (1..=len).any(|i| match_template(rest, &value[i..]));
+        }
+    }
+    match (template.chars().next(), value.chars().next()) {
+        (None, None) => true,
+        (Some(t), Some(v)) if t == v => {
+            match_template(&template[t.len_utf8()..], &value[v.len_utf8()..])
+        }
+        _ => false,
+    }
+}
+
+/// 完整域名，非默认线路时附带线路，如: www.example.com(telecom)
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == LINE_DEFAULT {
+            write!(f, "{}", self.name())
+        } else {
+            write!(f, "{}({})", self.name(), self.line)
+        }
+    }
+}
+
+/// 未获取到本地对应类型IP地址(如IPv6断开)时，已有解析记录的处理方式
+/// 对应类型的IP地址恢复后，暂停的记录自动启用，删除的记录自动重新创建
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum StalePolicy {
+    /// 保留解析记录
+    #[default]
+    Keep,
+    /// 暂停解析记录
+    Disable,
+    /// 删除解析记录
+    Delete,
+}
+
+impl FromStr for StalePolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(StalePolicy::Keep),
+            "disable" => Ok(StalePolicy::Disable),
+            "delete" => Ok(StalePolicy::Delete),
+            _ => Err("no match"),
+        }
+    }
+}
+
+/// 解析权重，取值范围 1-100
+#[throws(CommonError)]
+pub fn parse_weight(value: &str) -> i32 {
+    match value.parse::<i32>() {
+        Ok(weight) if (1..=100).contains(&weight) => weight,
+        _ => throw!(format!("解析权重{}不合法，取值范围为1-100", value)),
+    }
+}
+
+/// 解析类型列表，如: A,AAAA
+#[throws(CommonError)]
+pub fn parse_types(value: &str, separator: char) -> Vec<RecordType> {
+    let mut types: Vec<RecordType> = Vec::new();
+    for var in value.split(separator).filter(|s| !s.trim().is_empty()) {
+        let kind = var.parse::<RecordType>()?;
+        if !types.contains(&kind) {
+            types.push(kind);
+        }
+    }
+    if types.is_empty() {
+        throw!("解析类型不能为空");
+    }
+    types
+}
+
+/// DescribeDomainRecords 请求参数
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DescribeDomainRecordsRequest {
+    #[serde(rename(serialize = "DomainName"))]
+    pub domain_name: String,
+    #[serde(
+        rename(serialize = "PageNumber"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub page_number: Option<i32>,
+    #[serde(
+        rename(serialize = "PageSize"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub page_size: Option<i32>,
+    #[serde(rename(serialize = "KeyWord"), skip_serializing_if = "Option::is_none")]
+    pub key_word: Option<String>,
+    #[serde(
+        rename(serialize = "RRKeyWord"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rr_key_word: Option<String>,
+    #[serde(
+        rename(serialize = "TypeKeyWord"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub type_key_word: Option<String>,
+    #[serde(
+        rename(serialize = "ValueKeyWord"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub value_key_word: Option<String>,
+    /// 搜索模式: LIKE 或 EXACT
+    #[serde(
+        rename(serialize = "SearchMode"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub search_mode: Option<String>,
+    #[serde(rename(serialize = "Type"), skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(rename(serialize = "Line"), skip_serializing_if = "Option::is_none")]
+    pub line: Option<String>,
+}
+
+/// DescribeSubDomainRecords 请求参数
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DescribeSubDomainRecordsRequest {
+    /// 完整的子域名，如: www.example.com
+    #[serde(rename(serialize = "SubDomain"))]
+    pub sub_domain: String,
+    #[serde(
+        rename(serialize = "DomainName"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub domain_name: Option<String>,
+    #[serde(
+        rename(serialize = "PageNumber"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub page_number: Option<i32>,
+    #[serde(
+        rename(serialize = "PageSize"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub page_size: Option<i32>,
+    #[serde(rename(serialize = "Type"), skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(rename(serialize = "Line"), skip_serializing_if = "Option::is_none")]
+    pub line: Option<String>,
+}
+
+/// AddDomainRecord 请求参数
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AddDomainRecordRequest {
+    #[serde(rename(serialize = "DomainName"))]
+    pub domain_name: String,
+    #[serde(rename(serialize = "RR"))]
+    pub rr: String,
+    #[serde(rename(serialize = "Type"))]
+    pub kind: String,
+    #[serde(rename(serialize = "Value"))]
+    pub value: String,
+    #[serde(rename(serialize = "TTL"), skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+    #[serde(
+        rename(serialize = "Priority"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub priority: Option<i32>,
+    #[serde(rename(serialize = "Line"), skip_serializing_if = "Option::is_none")]
+    pub line: Option<String>,
+}
+
+/// UpdateDomainRecord 请求参数
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UpdateDomainRecordRequest {
+    #[serde(rename(serialize = "RecordId"))]
+    pub record_id: String,
+    #[serde(rename(serialize = "RR"))]
+    pub rr: String,
+    #[serde(rename(serialize = "Type"))]
+    pub kind: String,
+    #[serde(rename(serialize = "Value"))]
+    pub value: String,
+    #[serde(rename(serialize = "TTL"), skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+    #[serde(
+        rename(serialize = "Priority"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub priority: Option<i32>,
+    #[serde(rename(serialize = "Line"), skip_serializing_if = "Option::is_none")]
+    pub line: Option<String>,
+}
+
+/// DescribeDomainRecords 与 DescribeSubDomainRecords 响应
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RecordsResponse {
     #[serde(rename(deserialize = "RequestId"))]
-    request_id: String,
+    pub request_id: String,
     #[serde(rename(deserialize = "PageNumber"))]
-    page_number: i32,
+    pub page_number: i32,
     #[serde(rename(deserialize = "TotalCount"))]
-    total_count: i32,
+    pub total_count: i32,
     #[serde(rename(deserialize = "PageSize"))]
-    page_size: i32,
+    pub page_size: i32,
     #[serde(rename(deserialize = "DomainRecords"))]
-    pub(crate) domain_records: DomainRecords,
+    pub domain_records: DomainRecords,
 }
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DomainRecords {
     #[serde(rename(deserialize = "Record"))]
-    pub(crate) record: Vec<Record>,
+    pub record: Vec<Record>,
 }
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Record {
     #[serde(rename(deserialize = "RR"))]
-    rr: String,
+    pub rr: String,
     #[serde(rename(deserialize = "Line"))]
-    line: String,
+    pub line: String,
     #[serde(rename(deserialize = "Status"))]
-    status: String,
+    pub status: String,
     #[serde(rename(deserialize = "Locked"))]
-    locked: bool,
+    pub locked: bool,
     #[serde(rename(deserialize = "Type"))]
-    kind: String,
+    pub kind: String,
     #[serde(rename(deserialize = "DomainName"))]
-    domain_name: String,
+    pub domain_name: String,
     #[serde(rename(deserialize = "Value"))]
-    value: String,
+    pub value: String,
     #[serde(rename(deserialize = "RecordId"))]
-    record_id: String,
+    pub record_id: String,
     #[serde(rename(deserialize = "TTL"))]
-    ttl: i32,
-    #[serde(rename(deserialize = "Weight"))]
-    weight: i32,
+    pub ttl: i32,
+    #[serde(rename(deserialize = "Priority"), default)]
+    pub priority: Option<i32>,
+    #[serde(rename(deserialize = "Weight"), default)]
+    pub weight: Option<i32>,
+    #[serde(rename(deserialize = "Remark"), default)]
+    pub remark: Option<String>,
+}
+
+/// AddDomainRecord、UpdateDomainRecord 与 DeleteDomainRecord 响应
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecordIdResponse {
+    #[serde(rename(deserialize = "RequestId"))]
+    pub request_id: String,
+    #[serde(rename(deserialize = "RecordId"))]
+    pub record_id: String,
+}
+pub type AddDomainRecordResponse = RecordIdResponse;
+pub type UpdateDomainRecordResponse = RecordIdResponse;
+pub type DeleteDomainRecordResponse = RecordIdResponse;
+
+/// DeleteDomainRecord 请求参数
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeleteDomainRecordRequest {
+    #[serde(rename(serialize = "RecordId"))]
+    pub record_id: String,
+}
+
+/// 解析记录状态
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordStatus {
+    Enable,
+    Disable,
+}
+
+/// SetDomainRecordStatus 请求参数
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetDomainRecordStatusRequest {
+    #[serde(rename(serialize = "RecordId"))]
+    pub record_id: String,
+    #[serde(rename(serialize = "Status"))]
+    pub status: RecordStatus,
+}
+
+/// SetDomainRecordStatus 响应
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetDomainRecordStatusResponse {
+    #[serde(rename(deserialize = "RequestId"))]
+    pub request_id: String,
+    #[serde(rename(deserialize = "Status"))]
+    pub status: String,
+}
+
+/// SetDNSSLBStatus 请求参数，开启或关闭子域名的权重配置
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SetDNSSLBStatusRequest {
+    /// 完整的子域名，如: www.example.com
+    #[serde(rename(serialize = "SubDomain"))]
+    pub sub_domain: String,
+    #[serde(
+        rename(serialize = "DomainName"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub domain_name: Option<String>,
+    #[serde(rename(serialize = "Type"), skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(rename(serialize = "Line"), skip_serializing_if = "Option::is_none")]
+    pub line: Option<String>,
+    #[serde(rename(serialize = "Open"))]
+    pub open: bool,
+}
+
+/// SetDNSSLBStatus 响应
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetDNSSLBStatusResponse {
+    #[serde(rename(deserialize = "RequestId"))]
+    pub request_id: String,
+    #[serde(rename(deserialize = "RecordCount"), default)]
+    pub record_count: i64,
+    #[serde(rename(deserialize = "Open"), default)]
+    pub open: bool,
+}
+
+/// UpdateDNSSLBWeight 请求参数
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UpdateDNSSLBWeightRequest {
+    #[serde(rename(serialize = "RecordId"))]
+    pub record_id: String,
+    /// 解析权重(1-100)
+    #[serde(rename(serialize = "Weight"))]
+    pub weight: i32,
+}
+
+/// UpdateDomainRecordRemark 请求参数
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UpdateDomainRecordRemarkRequest {
+    #[serde(rename(serialize = "RecordId"))]
+    pub record_id: String,
+    /// 备注，为空时清除备注
+    #[serde(rename(serialize = "Remark"), skip_serializing_if = "Option::is_none")]
+    pub remark: Option<String>,
+}
+
+/// UpdateDNSSLBWeight 与 UpdateDomainRecordRemark 响应
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RequestIdResponse {
+    #[serde(rename(deserialize = "RequestId"))]
+    pub request_id: String,
+}
+pub type UpdateDNSSLBWeightResponse = RequestIdResponse;
+pub type UpdateDomainRecordRemarkResponse = RequestIdResponse;
+
+/// 单个域名的同步结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SyncOutcome {
+    /// 解析值未变化
+    Unchanged,
+    /// 已更新或删除解析记录
+    Updated,
+    /// 已创建解析记录
+    Created,
+}
+
+impl fmt::Display for SyncOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyncOutcome::Unchanged => f.write_str("无变化"),
+            SyncOutcome::Updated => f.write_str("已更新"),
+            SyncOutcome::Created => f.write_str("已创建"),
+        }
+    }
+}
+
+/// 获取主域名下的所有解析记录，自动翻页
+#[throws(AliyunError)]
+pub fn zone_records(client: &AliDnsClient, domain: &str) -> Vec<Record> {
+    let req = DescribeDomainRecordsRequest {
+        domain_name: domain.to_string(),
+        page_size: Some(RECORD_PAGE_SIZE),
+        ..Default::default()
+    };
+    client.domain_records(&req).collect::<Result<Vec<_>, _>>()?
+}
+
+/// 从主域名的解析记录中筛选出主机记录、类型与解析线路均匹配的记录
+fn matching<'a>(zone: &'a [Record], target: &Target, kind: &str) -> Vec<&'a Record> {
+    zone.iter()
+        .filter(|r| r.rr == target.rr && r.kind == kind && r.line == target.line)
+        .collect()
+}
+
+/// 同步策略
+#[derive(Clone, Debug, Default)]
+pub struct SyncOptions {
+    /// 删除域名下未配置的A/AAAA解析记录
+    pub prune_types: bool,
+    /// 同一类型存在多条解析记录时的处理方式
+    pub duplicates: DuplicatePolicy,
+    /// 外部维护的解析记录ID或解析值，不做任何修改
+    pub external: Vec<String>,
+    /// 备注标记，创建的解析记录会设置该备注
+    pub remark: Option<String>,
+    /// 仅修改带有备注标记的解析记录，其他记录作为冲突报告
+    pub owned_only: bool,
+    /// 暂停状态解析记录的处理方式
+    pub disabled: DisabledPolicy,
+    /// 创建解析记录时使用的TTL
+    pub ttl: u32,
+}
+
+impl SyncOptions {
+    /// 是否为外部维护的解析记录
+    pub fn is_external(&self, record: &Record) -> bool {
+        self.external
+            .iter()
+            .any(|v| *v == record.record_id || *v == record.value)
+    }
+}
+
+/// 暂停状态解析记录的处理方式
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum DisabledPolicy {
+    /// 保持暂停状态
+    Keep,
+    /// 解析值发生变化时启用
+    #[default]
+    OnChange,
+    /// 始终启用
+    Always,
+}
+
+impl DisabledPolicy {
+    /// 暂停状态的记录是否需要启用，changed 为解析值是否发生变化
+    fn enables(self, changed: bool) -> bool {
+        match self {
+            DisabledPolicy::Keep => false,
+            DisabledPolicy::OnChange => changed,
+            DisabledPolicy::Always => true,
+        }
+    }
 }
 
-/// 获取域名解析记录
+impl FromStr for DisabledPolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(DisabledPolicy::Keep),
+            "on-change" => Ok(DisabledPolicy::OnChange),
+            "always" => Ok(DisabledPolicy::Always),
+            _ => Err("no match"),
+        }
+    }
+}
+
+/// 同一类型存在多条解析记录时的处理方式
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum DuplicatePolicy {
+    /// 更新其中一条，删除其余记录
+    Delete,
+    /// 更新其中一条，暂停其余记录
+    Disable,
+    /// 不做修改并报告错误
+    #[default]
+    Refuse,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "delete" => Ok(DuplicatePolicy::Delete),
+            "disable" => Ok(DuplicatePolicy::Disable),
+            "refuse" => Ok(DuplicatePolicy::Refuse),
+            _ => Err("no match"),
+        }
+    }
+}
+
+/// 同步计划中的修改类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// 创建解析记录
+    Create,
+    /// 更新解析值
+    Update,
+    /// 启用暂停的解析记录
+    Enable,
+    /// 暂停解析记录
+    Disable,
+    /// 删除解析记录
+    Delete,
+    /// 开启权重配置并设置成员记录的权重
+    Weight,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Create => f.write_str("创建"),
+            Action::Update => f.write_str("更新"),
+            Action::Enable => f.write_str("启用"),
+            Action::Disable => f.write_str("暂停"),
+            Action::Delete => f.write_str("删除"),
+            Action::Weight => f.write_str("设置权重"),
+        }
+    }
+}
+
+/// 同步计划中的单项修改
+#[derive(Clone, Debug, Serialize)]
+pub struct Change {
+    pub action: Action,
+    /// 完整域名，如: www.example.com
+    pub name: String,
+    /// 解析类型
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// 解析线路
+    pub line: String,
+    /// 已有解析记录的ID，创建时为空
+    pub record_id: Option<String>,
+    /// 修改前的解析值，设置权重时为修改前的权重
+    pub old_value: Option<String>,
+    /// 修改后的解析值，设置权重时为修改后的权重
+    pub new_value: Option<String>,
+    /// 创建时为配置的TTL，其他修改为解析记录当前的TTL
+    pub ttl: u32,
+    /// MX 解析的优先级
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// 创建后设置的备注，成员模式下为成员标识
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remark: Option<String>,
+    /// 成员模式下创建后设置的解析权重
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<i32>,
+    /// 修改原因，如: 重复记录、未配置的解析类型
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'static str>,
+}
+
+impl Change {
+    /// 创建解析记录，创建后设置备注，成员模式下备注为成员标识
+    fn create(target: &Target, opt: &SyncOptions, kind: &str, value: String) -> Self {
+        Change {
+            action: Action::Create,
+            name: target.name(),
+            kind: kind.to_string(),
+            line: target.line.clone(),
+            record_id: None,
+            old_value: None,
+            new_value: Some(value),
+            ttl: opt.ttl,
+            priority: None,
+            remark: target.member.clone().or_else(|| opt.remark.clone()),
+            weight: None,
+            reason: None,
+        }
+    }
+
+    /// 针对已有解析记录的修改，默认解析值不变
+    fn of(action: Action, target: &Target, r: &Record) -> Self {
+        Change {
+            action,
+            name: target.name(),
+            kind: r.kind.clone(),
+            line: r.line.clone(),
+            record_id: Some(r.record_id.clone()),
+            old_value: Some(r.value.clone()),
+            new_value: None,
+            ttl: r.ttl as u32,
+            priority: None,
+            remark: None,
+            weight: None,
+            reason: None,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}: ",
+            self.action, self.name, self.kind, self.line
+        )?;
+        match (self.old_value.as_ref(), self.new_value.as_ref()) {
+            (Some(old), Some(new)) => write!(f, "{} -> {}", old, new)?,
+            (Some(value), None) | (None, Some(value)) => f.write_str(value)?,
+            (None, None) => {}
+        }
+        write!(f, ", TTL:{}", self.ttl)?;
+        if let Some(priority) = self.priority {
+            write!(f, ", 优先级:{}", priority)?;
+        }
+        if let Some(record_id) = self.record_id.as_ref() {
+            write!(f, ", record_id:{}", record_id)?;
+        }
+        if let Some(reason) = self.reason {
+            write!(f, " ({})", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// 单个域名的同步计划
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Plan {
+    /// 域名，非默认线路时包含线路，如: www.example.com(telecom)
+    pub name: String,
+    /// 需要执行的修改，按执行顺序排列
+    pub changes: Vec<Change>,
+    /// 无法自动处理的冲突，如: 重复记录、不带备注标记的记录
+    pub errors: Vec<String>,
+}
+
+/// 同步计划的输出格式
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum PlanFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+impl FromStr for PlanFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(PlanFormat::Human),
+            "json" => Ok(PlanFormat::Json),
+            _ => Err("no match"),
+        }
+    }
+}
+
+/// 同步单个域名: 先生成同步计划，再逐项执行
+/// zone 为该域名所属主域名的全部解析记录
 #[throws(CommonError)]
-pub fn list(
-    opt: &Options,
-    records: &HashMap<String, String>,
-) -> (Vec<Record>, HashMap<String, String>) {
-    let mut update_rs: Vec<Record> = Vec::new();
-    let mut create_rs: HashMap<String, String> = HashMap::new();
-    for (k, v) in records {
-        let data = get(opt, k.to_string(), v.to_string())?;
-        match data {
-            CommonResponse::Ok(mut data) => {
-                let ref mut r = data.domain_records.record;
-                if r.len() > 0 {
-                    update_rs.append(r);
-                } else {
-                    create_rs.insert(k.to_string(), v.to_string());
+pub fn sync(
+    client: &AliDnsClient,
+    ips: &IPOption,
+    target: &Target,
+    zone: &[Record],
+    opt: &SyncOptions,
+    timestamp: u64,
+) -> SyncOutcome {
+    apply(client, target, &plan(ips, target, zone, opt, timestamp))?
+}
+
+/// 生成单个域名的同步计划，不调用任何修改解析记录的接口
+/// 配置的解析类型存在记录时更新，否则创建；
+/// 未配置的地址类型默认保留，配置 prune_types 时删除；外部维护的记录不做修改
+/// zone 为该域名所属主域名的全部解析记录，timestamp 为本轮同步的时间戳，用于渲染解析值模板
+pub fn plan(
+    ips: &IPOption,
+    target: &Target,
+    zone: &[Record],
+    opt: &SyncOptions,
+    timestamp: u64,
+) -> Plan {
+    let mut plan = Plan {
+        name: target.to_string(),
+        ..Default::default()
+    };
+    if opt.owned_only && opt.remark.is_none() {
+        plan.errors
+            .push("仅修改带有备注标记的解析记录时，必须配置备注标记".to_string());
+        return plan;
+    }
+
+    let mut types = target.types.clone();
+    if target.existing_types
+        && !types.contains(&RecordType::AAAA)
+        && matching(zone, target, RecordType::AAAA.as_str())
+            .iter()
+            .any(|r| owned(target, opt, r))
+    {
+        types.push(RecordType::AAAA);
+    }
+
+    for kind in types.iter() {
+        match kind.ip_of(ips) {
+            Some(ip) => plan_value(&mut plan, target, zone, opt, *kind, ip.to_string()),
+            None => {
+                for r in unlocked(managed(zone, target, kind.as_str(), opt)).0 {
+                    plan.changes.extend(remove_stale(target, r));
                 }
             }
-            CommonResponse::Err(err) => log::error!("检查解析错误{:?}", err),
         }
     }
-    (update_rs, create_rs)
+    plan_static(&mut plan, target, zone, opt, ips, timestamp);
+
+    // 仅通过 --record 声明的域名没有配置地址解析类型，不清理其A/AAAA解析
+    if opt.prune_types && !target.types.is_empty() {
+        for kind in RecordType::ALL.iter().filter(|k| !types.contains(k)) {
+            for r in unlocked(managed(zone, target, kind.as_str(), opt)).0 {
+                plan.changes.push(Change {
+                    reason: Some("未配置的解析类型"),
+                    ..Change::of(Action::Delete, target, r)
+                });
+            }
+        }
+    }
+    plan
 }
 
-#[throws(CommonError)]
-fn get(
-    opt: &Options,
-    record: String,
-    domain: String,
-) -> CommonResponse<RecordsResponse, ErrorResponse> {
-    let ak_secret = opt.access_key_secret.clone().ok_or("缺少阿里云AK Secret")?;
-    let ak_id = opt.access_key_id.clone().ok_or("缺少阿里云AK ID")?;
-
-    let mut req_params = HashMap::new();
-    req_params.insert("Action".to_string(), "DescribeDomainRecords".to_string());
-    req_params.insert("DomainName".to_string(), domain);
-    req_params.insert("KeyWord".to_string(), record);
-    req_params.insert("SearchMode".to_string(), "EXACT".to_string());
-    req_params.insert("PageSize".to_string(), "100".to_string());
-
-    request::<CommonResponse<RecordsResponse, ErrorResponse>>(&ak_id, &ak_secret, req_params)?
-}
-
-/// 分离多个域名中解析记录与域名的关系
-/// @Return HashMap<String, String> key: 域名， value: 解析记录
-#[throws(CommonError)]
-pub fn split(opt: &Options) -> HashMap<String, String> {
-    let domains = opt.domains.clone();
-    let mut records: HashMap<String, String> = HashMap::new();
-    for domain in domains {
-        let segments: Vec<&str> = domain.split(".").collect();
-        let l = segments.len();
-        if l <= 2 {
-            throw!(format!("域名{}不合法，必须使用二级或以下域名", domain))
+/// 生成单个地址解析类型的修改: 不存在记录时创建，否则更新为期望的解析值
+fn plan_value(
+    plan: &mut Plan,
+    target: &Target,
+    zone: &[Record],
+    opt: &SyncOptions,
+    kind: RecordType,
+    value: String,
+) {
+    let weighted = target.member.is_some();
+    let kind = kind.as_str();
+    let (mut records, locked) = unlocked(managed(zone, target, kind, opt));
+    if opt.owned_only && target.member.is_none() {
+        let conflicts = foreign(zone, target, kind, opt);
+        if !conflicts.is_empty() {
+            plan.errors.push(conflict_error(kind, &conflicts));
+            if records.is_empty() {
+                return;
+            }
         }
+    }
+    if records.is_empty() && locked {
+        return;
+    }
+    if records.is_empty() {
+        plan.changes.push(Change {
+            weight: if weighted { Some(target.weight) } else { None },
+            ..Change::create(target, opt, kind, value)
+        });
+        return;
+    }
 
-        let record = segments[0..(l - 2)].join(".");
-        let top_level_domain = segments[(l - 2)..l].join(".");
-        records.insert(record, top_level_domain);
+    let deduplicated = records.len() > 1;
+    if records.len() > 1 {
+        if opt.duplicates == DuplicatePolicy::Refuse {
+            plan.errors.push(format!(
+                "存在{}条{}解析记录，拒绝更新: {}",
+                records.len(),
+                kind,
+                records
+                    .iter()
+                    .map(|r| r.record_id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ));
+            return;
+        }
+        // 优先保留解析值相同的记录，其次为启用状态的记录
+        records.sort_by_key(|r| (r.value != value, r.status == "DISABLE"));
+    }
+    // 保留的记录更新并启用后再删除或暂停其余重复记录，避免该域名短暂没有可用的解析
+    let duplicates: Vec<Change> = records
+        .drain(1..)
+        .flat_map(|r| remove_duplicate(target, r, opt.duplicates))
+        .collect();
+
+    let r = records[0];
+    let changed = r.value != value;
+    if changed {
+        plan.changes.push(Change {
+            new_value: Some(value),
+            ..Change::of(Action::Update, target, r)
+        });
+    } else {
+        log::info!("{}类型解析值相同，略过更新", r.kind);
+    }
+    // 暂停策略下IP地址恢复时，启用之前暂停的记录
+    let disabled = match target.stale {
+        StalePolicy::Disable => DisabledPolicy::Always,
+        _ => opt.disabled,
+    };
+    // 其余重复记录已删除或暂停，保留的记录必须启用，否则该域名没有可用的解析
+    if r.status == "DISABLE" && (deduplicated || disabled.enables(changed)) {
+        plan.changes.push(Change::of(Action::Enable, target, r));
+    }
+    plan.changes.extend(duplicates);
+    if weighted && r.weight != Some(target.weight) {
+        plan.changes.push(Change {
+            old_value: r.weight.map(|w| w.to_string()),
+            new_value: Some(target.weight.to_string()),
+            weight: Some(target.weight),
+            ..Change::of(Action::Weight, target, r)
+        });
     }
-    records
 }
 
-/// 更新解析记录
-#[throws(CommonError)]
-pub fn update_records(opt: &Options, ips: &ip::IPOption, records: &Vec<Record>) {
-    let ak_secret = opt.access_key_secret.clone().ok_or("缺少阿里云AK Secret")?;
-    let ak_id = opt.access_key_id.clone().ok_or("缺少阿里云AK ID")?;
-    #[allow(unused_assignments)]
-    let mut ipv4: Option<IpAddr> = None;
-    let mut ipv6: Option<IpAddr> = None;
-    match *ips {
-        IPOption::IPV4(v4) => ipv4 = Some(IpAddr::V4(v4)),
-        IPOption::IPAll(v4, v6) => {
-            ipv4 = Some(IpAddr::V4(v4));
-            ipv6 = Some(IpAddr::V6(v6));
-        }
-    }
-
-    for r in records {
-        let mut ip: Option<IpAddr> = None;
-        if r.kind == RECORD_TYPE_AAAA {
-            if None == ipv6 {
-                throw!("没有获取到local IPv6地址，无法更新AAAA解析");
-            }
-            if r.value == ipv6.unwrap().to_string() {
-                log::info!("{}类型解析值相同，略过更新", RECORD_TYPE_AAAA);
+/// 生成配置中声明的静态解析记录的修改
+/// 同名同类型的解析可以有多条(如 SPF 与域名验证的TXT解析、多个MX解析)，因此不按重复记录处理，
+/// 仅通过解析值模板识别各条配置对应的记录，每条已有记录最多对应一条配置，未识别到时创建
+fn plan_static(
+    plan: &mut Plan,
+    target: &Target,
+    zone: &[Record],
+    opt: &SyncOptions,
+    ips: &IPOption,
+    timestamp: u64,
+) {
+    let mut claimed: Vec<&str> = Vec::new();
+    for record in target.records.iter() {
+        let kind = record.kind.as_str();
+        let value = match record.render(ips, timestamp) {
+            Some(value) => value,
+            None => {
+                log::warn!(
+                    "没有获取到解析值模板引用的local IP地址，保留{}解析: {}",
+                    kind,
+                    target
+                );
                 continue;
             }
-            ip = ipv6;
-        } else if r.kind == RECORD_TYPE_A {
-            if r.value == ipv4.unwrap().to_string() {
-                log::info!("{}类型解析值相同，略过更新", RECORD_TYPE_A);
-                continue;
+        };
+        // 同名的CNAME解析只能有一条，已有的记录即为该配置对应的记录
+        let related = |r: &&Record| record.kind == StaticType::CNAME || record.matches(&r.value);
+        let records: Vec<&Record> = managed(zone, target, kind, opt)
+            .into_iter()
+            .filter(related)
+            .filter(|r| !claimed.contains(&r.record_id.as_str()))
+            .collect();
+        if opt.owned_only && target.member.is_none() {
+            let conflicts: Vec<&Record> = foreign(zone, target, kind, opt)
+                .into_iter()
+                .filter(related)
+                .collect();
+            if !conflicts.is_empty() {
+                plan.errors.push(conflict_error(kind, &conflicts));
+                if records.is_empty() {
+                    continue;
+                }
             }
-            ip = ipv4;
-        }
-        if let Err(e) = update_record(
-            &ip,
-            &ak_id,
-            &ak_secret,
-            &r.rr,
-            &r.domain_name,
-            &r.record_id,
-            &r.kind,
-        ) {
-            throw!(e);
+        }
+        let (mut records, locked) = unlocked(records);
+        if records.is_empty() && locked {
+            continue;
+        }
+        if records.is_empty() {
+            plan.changes.push(Change {
+                priority: record.priority,
+                ..Change::create(target, opt, kind, value)
+            });
+            continue;
+        }
+
+        // 优先选择解析值与优先级相同的记录，其次为启用状态的记录
+        let expected = record.kind.normalize(&value);
+        records.sort_by_key(|r| {
+            (
+                record.kind.normalize(&r.value) != expected,
+                r.priority != record.priority,
+                r.status == "DISABLE",
+            )
+        });
+        let r = records[0];
+        claimed.push(&r.record_id);
+        let changed = record.kind.normalize(&r.value) != expected
+            || (record.priority.is_some() && r.priority != record.priority);
+        if changed {
+            plan.changes.push(Change {
+                new_value: Some(value),
+                priority: record.priority,
+                ..Change::of(Action::Update, target, r)
+            });
         } else {
-            if r.status == "DISABLE" {
-                log::info!("设置类型{}解析记录{}为enable状态", r.kind, r.rr,);
-                if let Err(e) = enable_record(&ak_id, &ak_secret, &r.record_id) {
+            log::info!("{}类型解析值相同，略过更新", kind);
+        }
+        if r.status == "DISABLE" && opt.disabled.enables(changed) {
+            plan.changes.push(Change::of(Action::Enable, target, r));
+        }
+    }
+}
+
+/// 不带备注标记的记录阻止修改时的错误信息
+fn conflict_error(kind: &str, conflicts: &[&Record]) -> String {
+    format!(
+        "存在不带备注标记的{}解析记录，未做修改: {}",
+        kind,
+        conflicts
+            .iter()
+            .map(|r| format!("{}({})", r.record_id, r.value))
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+/// 是否为当前实例维护的解析记录
+/// 成员模式下不修改其他成员的记录；配置 owned_only 时不修改不带备注标记的记录
+fn owned(target: &Target, opt: &SyncOptions, r: &Record) -> bool {
+    match target.member.as_ref() {
+        Some(member) => r.remark.as_ref() == Some(member),
+        None if opt.owned_only => r.remark.is_some() && r.remark == opt.remark,
+        None => true,
+    }
+}
+
+/// 当前实例维护的解析记录，略过外部维护的记录
+fn managed<'a>(
+    zone: &'a [Record],
+    target: &Target,
+    kind: &str,
+    opt: &SyncOptions,
+) -> Vec<&'a Record> {
+    matching(zone, target, kind)
+        .into_iter()
+        .filter(|r| owned(target, opt, r))
+        .filter(|r| {
+            let external = opt.is_external(r);
+            if external {
+                log::info!("{}解析记录{}由外部维护，略过", kind, r.record_id);
+            }
+            !external
+        })
+        .collect()
+}
+
+/// 不由当前实例维护，且不是外部维护的解析记录
+fn foreign<'a>(
+    zone: &'a [Record],
+    target: &Target,
+    kind: &str,
+    opt: &SyncOptions,
+) -> Vec<&'a Record> {
+    matching(zone, target, kind)
+        .into_iter()
+        .filter(|r| !owned(target, opt, r) && !opt.is_external(r))
+        .collect()
+}
+
+/// 锁定的记录无法修改，但视为已存在，不再创建新记录
+/// @Return 未锁定的记录，以及是否存在锁定的记录
+fn unlocked(records: Vec<&Record>) -> (Vec<&Record>, bool) {
+    let locked = records.iter().any(|r| r.locked);
+    let records = records
+        .into_iter()
+        .filter(|r| {
+            if r.locked {
+                log::warn!("{}解析记录{}已锁定，略过", r.kind, r.record_id);
+            }
+            !r.locked
+        })
+        .collect();
+    (records, locked)
+}
+
+/// 未获取到本地对应类型IP地址时，按策略保留、暂停或删除解析记录
+fn remove_stale(target: &Target, r: &Record) -> Option<Change> {
+    let action = match target.stale {
+        StalePolicy::Keep => {
+            log::warn!("没有获取到local IP地址，保留{}解析: {}", r.kind, r.value);
+            return None;
+        }
+        StalePolicy::Disable if r.status != "DISABLE" => Action::Disable,
+        StalePolicy::Disable => return None,
+        StalePolicy::Delete => Action::Delete,
+    };
+    Some(Change {
+        reason: Some("没有获取到local IP地址"),
+        ..Change::of(action, target, r)
+    })
+}
+
+/// 删除或暂停重复记录的修改原因
+const DUPLICATE_REASON: &str = "重复的解析记录";
+
+/// 按策略删除或暂停重复的解析记录
+fn remove_duplicate(target: &Target, r: &Record, policy: DuplicatePolicy) -> Option<Change> {
+    let action = match policy {
+        DuplicatePolicy::Delete => Action::Delete,
+        DuplicatePolicy::Disable if r.status != "DISABLE" => Action::Disable,
+        _ => return None,
+    };
+    Some(Change {
+        reason: Some(DUPLICATE_REASON),
+        ..Change::of(action, target, r)
+    })
+}
+
+/// 执行同步计划，单项修改失败不影响其他修改
+/// 计划中的冲突与执行失败的错误合并后返回
+#[throws(CommonError)]
+pub fn apply(client: &AliDnsClient, target: &Target, plan: &Plan) -> SyncOutcome {
+    apply_with(plan, |change| execute(client, target, change))?
+}
+
+/// 按顺序执行同步计划中的修改，execute 执行单项修改
+#[throws(CommonError)]
+fn apply_with<F>(plan: &Plan, mut execute: F) -> SyncOutcome
+where
+    F: FnMut(&Change) -> Result<(), AliyunError>,
+{
+    let mut outcome = SyncOutcome::Unchanged;
+    let mut errors = plan.errors.clone();
+    // 保留的记录更新或启用失败的解析类型
+    let mut failed: Vec<&str> = Vec::new();
+    for change in plan.changes.iter() {
+        // 保留的记录未能更新或启用时不处理其余重复记录，避免该域名没有可用的解析
+        if change.reason == Some(DUPLICATE_REASON) && failed.contains(&change.kind.as_str()) {
+            log::warn!(
+                "{}的{}解析记录未能更新或启用，保留重复的解析记录: {}",
+                change.name,
+                change.kind,
+                change.record_id.as_deref().unwrap_or_default()
+            );
+            continue;
+        }
+        match execute(change) {
+            Ok(_) if change.action == Action::Create => outcome = SyncOutcome::Created,
+            Ok(_) => outcome = outcome.max(SyncOutcome::Updated),
+            Err(e) => {
+                if change.action == Action::Update || change.action == Action::Enable {
+                    failed.push(&change.kind);
+                }
+                if change.action == Action::Enable {
+                    // 启用失败不影响解析值的更新，仅记录日志
                     log::error!(
                         "设置类型{}解析记录{}为enable状态出错: {:?}",
-                        r.kind,
-                        r.rr,
+                        change.kind,
+                        change.name,
                         e,
                     );
+                } else {
+                    errors.push(e.to_string());
                 }
             }
+        }
+    }
+
+    if !errors.is_empty() {
+        throw!(errors.join("; "));
+    }
+    outcome
+}
+
+/// 执行单项修改
+#[throws(AliyunError)]
+fn execute(client: &AliDnsClient, target: &Target, change: &Change) {
+    let record_id = change.record_id.as_deref().unwrap_or_default();
+    let value = change.old_value.as_deref().unwrap_or_default();
+    match change.action {
+        Action::Create => {
+            let ip = change.new_value.as_deref().unwrap_or_default();
+            log::info!("开始创建{}解析! 域名: {}, ip:{}", change.kind, target, ip);
+            let record_id = create_record(client, target, change)?;
+            log::info!("创建{}解析成功! 域名: {}, ip:{}", change.kind, target, ip);
+            if let Some(remark) = change.remark.as_ref() {
+                log::info!("设置{}解析记录{}的备注: {}", change.kind, record_id, remark);
+                let req = UpdateDomainRecordRemarkRequest {
+                    record_id: record_id.clone(),
+                    remark: Some(remark.clone()),
+                };
+                // 不带备注的记录不会被识别为当前实例维护的记录，删除后由下次同步重新创建
+                if let Err(e) = client.update_domain_record_remark(&req) {
+                    log::error!(
+                        "设置{}解析记录{}的备注出错，删除新建的记录: {:?}",
+                        change.kind,
+                        record_id,
+                        e
+                    );
+                    client.delete_domain_record(&DeleteDomainRecordRequest { record_id })?;
+                    throw!(e);
+                }
+            }
+            if change.weight.is_some() {
+                set_member_weight(client, target, &change.kind, &record_id)?;
+            }
+        }
+        Action::Update => {
+            update_record(client, target, change)?;
             log::info!(
-                "更新解析记录成功! 域名: {}, 解析值:{}, ips:{:?}",
-                &r.domain_name,
-                &r.rr,
-                ip,
+                "更新解析记录成功! 域名: {}, 解析值:{}, ip:{}",
+                target.domain,
+                target.rr,
+                change.new_value.as_deref().unwrap_or_default(),
             );
         }
+        Action::Enable => {
+            log::info!("设置类型{}解析记录{}为enable状态", change.kind, target.rr);
+            client.set_domain_record_status(&SetDomainRecordStatusRequest {
+                record_id: record_id.to_string(),
+                status: RecordStatus::Enable,
+            })?;
+        }
+        Action::Disable | Action::Delete => {
+            log::info!(
+                "{}，{}{}解析: {}, record_id:{}",
+                change.reason.unwrap_or_default(),
+                change.action,
+                change.kind,
+                value,
+                record_id
+            );
+            if change.action == Action::Delete {
+                client.delete_domain_record(&DeleteDomainRecordRequest {
+                    record_id: record_id.to_string(),
+                })?;
+            } else {
+                client.set_domain_record_status(&SetDomainRecordStatusRequest {
+                    record_id: record_id.to_string(),
+                    status: RecordStatus::Disable,
+                })?;
+            }
+        }
+        Action::Weight => set_member_weight(client, target, &change.kind, record_id)?,
     }
 }
 
-/// 组装更新解析记录所需的参数
+/// 按主域名分组需要更新的域名
+/// @Return HashMap<String, Vec<Target>> key: 主域名， value: 该主域名下的目标域名
 #[throws(CommonError)]
-fn update_record(
-    ip: &Option<IpAddr>,
-    ak_id: &str,
-    ak_secret: &str,
-    record: &str,
-    domain: &str,
-    record_id: &str,
-    kind: &str,
-) {
-    let ip = ip.expect("更新解析时，传递空的IP地址");
+pub fn split(opt: &Options) -> HashMap<String, Vec<Target>> {
+    let mut targets: HashMap<String, Vec<Target>> = HashMap::new();
+    let defaults = opt.target_defaults();
+    for spec in opt.domains.iter() {
+        let target = Target::parse(spec, &defaults)?;
+        // 暂停的记录在IP地址恢复时无法区分是否为手动暂停，保持暂停会导致解析永远不会恢复
+        if target.stale == StalePolicy::Disable && opt.disabled == DisabledPolicy::Keep {
+            throw!(format!(
+                "域名{}的stale=disable与--disabled keep冲突，IP地址恢复时需要启用暂停的解析",
+                target
+            ));
+        }
+        // 未显式配置解析类型时仅在AAAA解析已存在时维护，删除后IP地址恢复也不会重新创建
+        if target.existing_types && target.stale == StalePolicy::Delete {
+            throw!(format!(
+                "域名{}未配置解析类型，stale=delete删除的AAAA解析在IP地址恢复后不会重新创建，需通过--record-types或“域名:类型”指定解析类型",
+                target
+            ));
+        }
+        let zone = targets.entry(target.domain.clone()).or_default();
+        if zone
+            .iter()
+            .any(|t| t.rr == target.rr && t.line == target.line)
+        {
+            throw!(format!("域名{}重复配置", target));
+        }
+        zone.push(target);
+    }
+    // 静态解析记录与同名的地址解析共用同一个域名配置
+    for spec in opt.records.iter() {
+        let (name, record) = StaticRecord::parse(spec)?;
+        let target = Target::of_static(&name, &defaults)?;
+        let zone = targets.entry(target.domain.clone()).or_default();
+        let index = match zone
+            .iter()
+            .position(|t| t.rr == target.rr && t.line == target.line)
+        {
+            Some(index) => index,
+            None => {
+                zone.push(target);
+                zone.len() - 1
+            }
+        };
+        let target = &mut zone[index];
+        // 同名同类型可配置多条解析(如多个TXT、MX解析)，但CNAME解析只能有一条
+        if target.records.iter().any(|r| {
+            r.kind == record.kind
+                && (record.kind == StaticType::CNAME || r.template == record.template)
+        }) {
+            throw!(format!("域名{}重复配置{}解析", target, record.kind));
+        }
+        target.records.push(record);
+    }
+    targets
+}
 
-    let mut req_params = HashMap::new();
-    req_params.insert("Action".to_string(), "UpdateDomainRecord".to_string());
-    req_params.insert("DomainName".to_string(), domain.to_string());
-    req_params.insert("RR".to_string(), record.to_string());
-    req_params.insert("RecordId".to_string(), record_id.to_string());
-    req_params.insert("Value".to_string(), ip.to_string());
-    req_params.insert("Type".to_string(), kind.to_string());
+/// 组装更新解析记录所需的参数，保留解析记录原有的TTL
+#[throws(AliyunError)]
+fn update_record(client: &AliDnsClient, target: &Target, change: &Change) {
+    let req = UpdateDomainRecordRequest {
+        record_id: change.record_id.clone().unwrap_or_default(),
+        rr: target.rr.clone(),
+        kind: change.kind.clone(),
+        value: change.new_value.clone().unwrap_or_default(),
+        ttl: Some(change.ttl),
+        priority: change.priority,
+        line: Some(change.line.clone()),
+    };
 
-    log::info!("更新{}解析: {}, record_id:{}", kind, ip, record_id);
-    request::<Value>(ak_id, ak_secret, req_params)?
+    log::info!(
+        "更新{}解析: {}, record_id:{}",
+        req.kind,
+        req.value,
+        req.record_id
+    );
+    client.update_domain_record(&req)?;
 }
 
-/// 设置解析记录状态为enable
-#[throws(CommonError)]
-fn enable_record(ak_id: &str, ak_secret: &str, record_id: &str) {
-    let mut req_params = HashMap::new();
-    req_params.insert("Action".to_string(), "SetDomainRecordStatus".to_string());
-    req_params.insert("Status".to_string(), "Enable".to_string());
-    req_params.insert("RecordId".to_string(), record_id.to_string());
-    request::<Value>(ak_id, ak_secret, req_params)?
+/// 开启子域名的权重配置并设置当前成员记录的权重
+#[throws(AliyunError)]
+fn set_member_weight(client: &AliDnsClient, target: &Target, kind: &str, record_id: &str) {
+    let req = SetDNSSLBStatusRequest {
+        sub_domain: target.name(),
+        domain_name: Some(target.domain.clone()),
+        kind: Some(kind.to_string()),
+        line: Some(target.line.clone()),
+        open: true,
+    };
+    // 仅一条记录时无法开启权重配置，不影响设置权重
+    if let Err(e) = client.set_dns_slb_status(&req) {
+        log::warn!("开启{}的{}解析权重配置失败: {}", target, kind, e);
+    }
+    log::info!("设置{}解析记录{}的权重: {}", kind, record_id, target.weight);
+    client.update_dns_slb_weight(&UpdateDNSSLBWeightRequest {
+        record_id: record_id.to_string(),
+        weight: target.weight,
+    })?;
 }
 
-/// 创建解析记录
-#[throws(CommonError)]
-pub fn create_records(opt: &Options, ips: &ip::IPOption, records: &HashMap<String, String>) {
-    let ak_secret = opt.access_key_secret.clone().ok_or("缺少阿里云AK Secret")?;
-    let ak_id = opt.access_key_id.clone().ok_or("缺少阿里云AK ID")?;
-    for (record, domain) in records {
-        log::info!(
-            "开始创建解析! 域名: {}, 解析值:{}, ips:{:?}",
-            domain,
-            record,
-            ips
-        );
-        if let Err(e) = create_record(ips, &ak_id, &ak_secret, record, domain) {
-            throw!(e);
-        } else {
-            log::info!(
-                "创建解析成功! 域名: {}, 解析值:{}, ips:{:?}",
-                domain,
-                record,
-                ips
-            );
+/// 组装参数，按计划创建解析记录
+/// @Return 解析记录ID
+#[throws(AliyunError)]
+fn create_record(client: &AliDnsClient, target: &Target, change: &Change) -> String {
+    let req = AddDomainRecordRequest {
+        domain_name: target.domain.clone(),
+        rr: target.rr.clone(),
+        kind: change.kind.clone(),
+        value: change.new_value.clone().unwrap_or_default(),
+        ttl: Some(change.ttl),
+        priority: change.priority,
+        line: Some(change.line.clone()),
+    };
+    client.add_domain_record(&req)?.record_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Mode;
+    use std::net::Ipv4Addr;
+
+    const TIMESTAMP: u64 = 1700000000;
+    fn ips() -> IPOption {
+        IPOption::IPAll(Ipv4Addr::new(1, 2, 3, 4), "2001:db8::1".parse().unwrap())
+    }
+
+    fn opt() -> SyncOptions {
+        SyncOptions {
+            ttl: 600,
+            ..Default::default()
         }
     }
-}
 
-/// 组装参数; 根据IPv4 IPv6类型分别创建记录
-#[throws(CommonError)]
-fn create_record(ips: &ip::IPOption, ak_id: &str, ak_secret: &str, record: &str, domain: &str) {
-    let mut req_params = HashMap::new();
-    req_params.insert("Action".to_string(), "AddDomainRecord".to_string());
-    req_params.insert("DomainName".to_string(), domain.to_string());
-    req_params.insert("RR".to_string(), record.to_string());
-
-    match ips {
-        IPOption::IPV4(v4) => {
-            req_params.insert("Value".to_string(), v4.to_string());
-            req_params.insert("Type".to_string(), RECORD_TYPE_A.to_string());
-            let _r = request::<Value>(ak_id, ak_secret, req_params)?;
-        }
-        IPOption::IPAll(v4, v6) => {
-            let mut v6_params = req_params.clone();
-            v6_params.insert("Value".to_string(), v6.to_string());
-            v6_params.insert("Type".to_string(), RECORD_TYPE_AAAA.to_string());
-            let _r = request::<Value>(ak_id, ak_secret, v6_params)?;
-
-            let mut v4_params = req_params;
-            v4_params.insert("Value".to_string(), v4.to_string());
-            v4_params.insert("Type".to_string(), RECORD_TYPE_A.to_string());
-            let _r = request::<Value>(ak_id, ak_secret, v4_params)?;
+    fn defaults() -> TargetDefaults {
+        TargetDefaults {
+            types: None,
+            stale: StalePolicy::Keep,
+            line: LINE_DEFAULT.to_string(),
+            member: None,
+            weight: 1,
+        }
+    }
+
+    /// 通过 --domain 声明的域名
+    fn domain(spec: &str) -> Target {
+        Target::parse(spec, &defaults()).unwrap()
+    }
+
+    fn record(id: &str, rr: &str, kind: &str, value: &str) -> Record {
+        Record {
+            rr: rr.to_string(),
+            line: LINE_DEFAULT.to_string(),
+            status: "ENABLE".to_string(),
+            locked: false,
+            kind: kind.to_string(),
+            domain_name: "example.com".to_string(),
+            value: value.to_string(),
+            record_id: id.to_string(),
+            ttl: 600,
+            priority: None,
+            weight: None,
+            remark: None,
+        }
+    }
+
+    /// 修改摘要: (操作, 记录ID, 修改后的值)
+    fn summary(plan: &Plan) -> Vec<(Action, &str, &str)> {
+        plan.changes
+            .iter()
+            .map(|c| {
+                (
+                    c.action,
+                    c.record_id.as_deref().unwrap_or_default(),
+                    c.new_value.as_deref().unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    fn plan_of(zone: &[Record], spec: &str) -> Plan {
+        super::plan(&ips(), &domain(spec), zone, &opt(), TIMESTAMP)
+    }
+
+    fn disabled(r: Record) -> Record {
+        Record {
+            status: "DISABLE".to_string(),
+            ..r
         }
     }
+
+    /// 仅通过 --record 声明的域名
+    fn static_target(name: &str, specs: &[&str]) -> Target {
+        Target {
+            records: specs
+                .iter()
+                .map(|spec| StaticRecord::parse(spec).unwrap().1)
+                .collect(),
+            ..Target::of_static(name, &defaults()).unwrap()
+        }
+    }
+
+    #[test]
+    fn plan_creates_missing_record() {
+        let plan = super::plan(&ips(), &domain("www.example.com"), &[], &opt(), TIMESTAMP);
+        assert_eq!(summary(&plan), vec![(Action::Create, "", "1.2.3.4")]);
+        assert_eq!(plan.changes[0].ttl, 600);
+        assert!(plan.errors.is_empty());
+    }
+
+    #[test]
+    fn plan_updates_changed_value() {
+        let zone = vec![
+            record("1", "www", "A", "9.9.9.9"),
+            record("2", "www", "AAAA", "2001:db8::1"),
+        ];
+        let target = domain("www.example.com:A+AAAA");
+        let plan = super::plan(&ips(), &target, &zone, &opt(), TIMESTAMP);
+        assert_eq!(summary(&plan), vec![(Action::Update, "1", "1.2.3.4")]);
+    }
+
+    #[test]
+    fn plan_updates_existing_aaaa_without_configured_types() {
+        let zone = vec![
+            record("1", "www", "A", "1.2.3.4"),
+            record("2", "www", "AAAA", "2001:db8::2"),
+        ];
+        let plan = super::plan(&ips(), &domain("www.example.com"), &zone, &opt(), TIMESTAMP);
+        assert_eq!(summary(&plan), vec![(Action::Update, "2", "2001:db8::1")]);
+
+        // 显式配置解析类型时仅维护配置的类型
+        let plan = plan_of(&zone, "www.example.com:A");
+        assert!(plan.changes.is_empty());
+    }
+
+    #[test]
+    fn plan_refuses_duplicates_by_default() {
+        let zone = vec![
+            record("1", "www", "A", "1.2.3.4"),
+            record("2", "www", "A", "5.6.7.8"),
+        ];
+        let plan = plan_of(&zone, "www.example.com");
+        assert!(plan.changes.is_empty());
+        assert_eq!(plan.errors.len(), 1);
+        assert!(plan.errors[0].contains("1,2"));
+    }
+
+    #[test]
+    fn plan_keeps_enabled_record_when_removing_duplicates() {
+        // 解析值相同的记录已暂停，另一条启用的记录解析值已过期
+        let zone = vec![
+            disabled(record("1", "www", "A", "1.2.3.4")),
+            record("2", "www", "A", "5.6.7.8"),
+        ];
+        let target = domain("www.example.com");
+        let plan_with = |duplicates| {
+            let opt = SyncOptions {
+                duplicates,
+                disabled: DisabledPolicy::Keep,
+                ..opt()
+            };
+            super::plan(&ips(), &target, &zone, &opt, TIMESTAMP)
+        };
+
+        // 保留的记录先启用，再暂停或删除其余记录
+        assert_eq!(
+            summary(&plan_with(DuplicatePolicy::Disable)),
+            vec![(Action::Enable, "1", ""), (Action::Disable, "2", "")]
+        );
+        assert_eq!(
+            summary(&plan_with(DuplicatePolicy::Delete)),
+            vec![(Action::Enable, "1", ""), (Action::Delete, "2", "")]
+        );
+    }
+
+    #[test]
+    fn plan_skips_external_records() {
+        let zone = vec![record("1", "www", "A", "9.9.9.9")];
+        let opt = SyncOptions {
+            external: vec!["1".to_string()],
+            ..opt()
+        };
+        let plan = super::plan(&ips(), &domain("www.example.com"), &zone, &opt, TIMESTAMP);
+        assert_eq!(summary(&plan), vec![(Action::Create, "", "1.2.3.4")]);
+    }
+
+    /// 执行计划，fail 中的操作执行失败
+    /// @Return (执行结果, 执行过的操作)
+    fn apply_failing(plan: &Plan, fail: &[Action]) -> (Result<SyncOutcome, String>, Vec<Action>) {
+        let mut executed = Vec::new();
+        let result = apply_with(plan, |change| {
+            executed.push(change.action);
+            if fail.contains(&change.action) {
+                Err(AliyunError::Request("request failed".into()))
+            } else {
+                Ok(())
+            }
+        });
+        (result.map_err(|e| e.to_string()), executed)
+    }
+
+    #[test]
+    fn apply_keeps_duplicates_when_kept_record_fails() {
+        let zone = vec![
+            disabled(record("1", "www", "A", "9.9.9.9")),
+            record("2", "www", "A", "5.6.7.8"),
+        ];
+        let opt = SyncOptions {
+            duplicates: DuplicatePolicy::Delete,
+            ..opt()
+        };
+        let plan = super::plan(&ips(), &domain("www.example.com"), &zone, &opt, TIMESTAMP);
+        assert_eq!(
+            summary(&plan),
+            vec![(Action::Update, "2", "1.2.3.4"), (Action::Delete, "1", ""),]
+        );
+
+        let (result, executed) = apply_failing(&plan, &[]);
+        assert_eq!(result, Ok(SyncOutcome::Updated));
+        assert_eq!(executed, vec![Action::Update, Action::Delete]);
+
+        // 更新保留的记录失败时不删除其余重复记录
+        let (result, executed) = apply_failing(&plan, &[Action::Update]);
+        assert!(result.is_err());
+        assert_eq!(executed, vec![Action::Update]);
+    }
+
+    #[test]
+    fn apply_keeps_duplicates_when_enabling_kept_record_fails() {
+        let zone = vec![
+            disabled(record("1", "www", "A", "1.2.3.4")),
+            record("2", "www", "A", "5.6.7.8"),
+        ];
+        let opt = SyncOptions {
+            duplicates: DuplicatePolicy::Disable,
+            ..opt()
+        };
+        let plan = super::plan(&ips(), &domain("www.example.com"), &zone, &opt, TIMESTAMP);
+
+        let (result, executed) = apply_failing(&plan, &[Action::Enable]);
+        assert_eq!(result, Ok(SyncOutcome::Unchanged));
+        assert_eq!(executed, vec![Action::Enable]);
+    }
+
+    #[test]
+    fn plan_stale_policies() {
+        let ips = IPOption::IPV4(Ipv4Addr::new(1, 2, 3, 4));
+        let zone = vec![record("1", "v6", "AAAA", "2001:db8::1")];
+        let stale = |spec: &str| super::plan(&ips, &domain(spec), &zone, &opt(), TIMESTAMP);
+
+        assert!(stale("v6.example.com:AAAA?stale=keep").changes.is_empty());
+        assert_eq!(
+            summary(&stale("v6.example.com:AAAA?stale=disable")),
+            vec![(Action::Disable, "1", "")]
+        );
+        assert_eq!(
+            summary(&stale("v6.example.com:AAAA?stale=delete")),
+            vec![(Action::Delete, "1", "")]
+        );
+
+        // 已暂停的记录不再重复暂停
+        let zone = vec![disabled(record("1", "v6", "AAAA", "2001:db8::1"))];
+        let plan = super::plan(
+            &ips,
+            &domain("v6.example.com:AAAA?stale=disable"),
+            &zone,
+            &opt(),
+            TIMESTAMP,
+        );
+        assert!(plan.changes.is_empty());
+    }
+
+    #[test]
+    fn plan_enables_stale_record_when_ip_recovers() {
+        let zone = vec![disabled(record("1", "v6", "AAAA", "2001:db8::1"))];
+        let plan = plan_of(&zone, "v6.example.com:AAAA?stale=disable");
+        assert_eq!(summary(&plan), vec![(Action::Enable, "1", "")]);
+
+        // 未配置暂停策略时，解析值不变则保持暂停
+        let plan = plan_of(&zone, "v6.example.com:AAAA");
+        assert!(plan.changes.is_empty());
+    }
+
+    #[test]
+    fn plan_recreates_deleted_record_when_ip_recovers() {
+        // stale=delete 删除AAAA解析后，IPv6地址恢复时重新创建
+        let zone = vec![record("1", "dual", "A", "1.2.3.4")];
+        let plan = plan_of(&zone, "dual.example.com:A+AAAA?stale=delete");
+        assert_eq!(summary(&plan), vec![(Action::Create, "", "2001:db8::1")]);
+    }
+
+    fn split_domains(domains: &[&str]) -> Result<HashMap<String, Vec<Target>>, CommonError> {
+        let mut options = Options::new(Mode::Cli);
+        options.domains = domains.iter().map(|d| d.to_string()).collect();
+        split(&options)
+    }
+
+    #[test]
+    fn split_rejects_stale_delete_without_types() {
+        let error = split_domains(&["www.example.com?stale=delete"])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("www.example.com"));
+        assert!(split_domains(&["www.example.com:A+AAAA?stale=delete"]).is_ok());
+        assert!(split_domains(&["www.example.com?stale=disable"]).is_ok());
+    }
+
+    #[test]
+    fn plan_matches_records_by_line() {
+        let zone = vec![
+            record("1", "www", "A", "9.9.9.9"),
+            Record {
+                line: "telecom".to_string(),
+                ..record("2", "www", "A", "8.8.8.8")
+            },
+        ];
+
+        let plan = plan_of(&zone, "www.example.com:A?line=telecom");
+        assert_eq!(summary(&plan), vec![(Action::Update, "2", "1.2.3.4")]);
+        assert_eq!(plan.changes[0].line, "telecom");
+
+        let plan = plan_of(&zone, "www.example.com:A");
+        assert_eq!(summary(&plan), vec![(Action::Update, "1", "1.2.3.4")]);
+
+        // 其他线路的记录不视为已存在
+        let plan = plan_of(&zone, "www.example.com:A?line=unicom");
+        assert_eq!(summary(&plan), vec![(Action::Create, "", "1.2.3.4")]);
+        assert_eq!(plan.changes[0].line, "unicom");
+    }
+
+    #[test]
+    fn split_rejects_duplicate_name_and_line() {
+        let error = split_domains(&["www.example.com", "www.example.com:AAAA"])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("www.example.com"));
+
+        let targets = split_domains(&["www.example.com", "www.example.com?line=telecom"]).unwrap();
+        assert_eq!(targets["example.com"].len(), 2);
+        assert!(split_domains(&[
+            "www.example.com?line=telecom",
+            "www.example.com?line=telecom"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn plan_member_weight() {
+        let target = domain("www.example.com?member=node1&weight=10");
+
+        let plan = super::plan(&ips(), &target, &[], &opt(), TIMESTAMP);
+        assert_eq!(summary(&plan), vec![(Action::Create, "", "1.2.3.4")]);
+        assert_eq!(plan.changes[0].remark.as_deref(), Some("node1"));
+        assert_eq!(plan.changes[0].weight, Some(10));
+
+        // 仅修改当前成员的记录
+        let member = |id, remark: &str, value| Record {
+            remark: Some(remark.to_string()),
+            weight: Some(5),
+            ..record(id, "www", "A", value)
+        };
+        let zone = vec![
+            member("1", "node1", "1.2.3.4"),
+            member("2", "node2", "5.6.7.8"),
+        ];
+        let plan = super::plan(&ips(), &target, &zone, &opt(), TIMESTAMP);
+        assert_eq!(summary(&plan), vec![(Action::Weight, "1", "10")]);
+    }
+
+    #[test]
+    fn plan_owned_only_reports_conflicts() {
+        let opt = SyncOptions {
+            remark: Some("ddns".to_string()),
+            owned_only: true,
+            ..opt()
+        };
+        let target = domain("www.example.com");
+
+        let zone = vec![record("1", "www", "A", "9.9.9.9")];
+        let plan = super::plan(&ips(), &target, &zone, &opt, TIMESTAMP);
+        assert!(plan.changes.is_empty());
+        assert_eq!(plan.errors.len(), 1);
+        assert!(plan.errors[0].contains("1(9.9.9.9)"));
+
+        // 存在带备注标记的记录时仍然更新该记录，同时报告冲突
+        let owned = Record {
+            remark: Some("ddns".to_string()),
+            ..record("2", "www", "A", "5.6.7.8")
+        };
+        let zone = vec![record("1", "www", "A", "9.9.9.9"), owned];
+        let plan = super::plan(&ips(), &target, &zone, &opt, TIMESTAMP);
+        assert_eq!(summary(&plan), vec![(Action::Update, "2", "1.2.3.4")]);
+        assert_eq!(plan.errors.len(), 1);
+
+        // 未配置备注标记时拒绝同步
+        let opt = SyncOptions {
+            remark: None,
+            ..opt
+        };
+        let plan = super::plan(&ips(), &target, &zone, &opt, TIMESTAMP);
+        assert!(plan.changes.is_empty());
+        assert_eq!(plan.errors.len(), 1);
+    }
+
+    #[test]
+    fn plan_disabled_policies() {
+        let zone = vec![disabled(record("1", "www", "A", "9.9.9.9"))];
+        let target = domain("www.example.com");
+        let plan_with = |disabled| {
+            let opt = SyncOptions { disabled, ..opt() };
+            summary(&super::plan(&ips(), &target, &zone, &opt, TIMESTAMP))
+                .into_iter()
+                .map(|(action, _, _)| action)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(plan_with(DisabledPolicy::Keep), vec![Action::Update]);
+        assert_eq!(
+            plan_with(DisabledPolicy::OnChange),
+            vec![Action::Update, Action::Enable]
+        );
+    }
+
+    #[test]
+    fn plan_skips_locked_records() {
+        let zone = vec![Record {
+            locked: true,
+            ..record("1", "www", "A", "9.9.9.9")
+        }];
+        let plan = plan_of(&zone, "www.example.com");
+        assert!(plan.changes.is_empty());
+        assert!(plan.errors.is_empty());
+    }
+
+    #[test]
+    fn split_rejects_stale_disable_with_disabled_keep() {
+        let mut options = Options::new(Mode::Cli);
+        options.domains = vec!["www.example.com?stale=disable".to_string()];
+        options.disabled = DisabledPolicy::Keep;
+        assert!(split(&options).is_err());
+
+        options.disabled = DisabledPolicy::OnChange;
+        assert!(split(&options).is_ok());
+    }
+
+    #[test]
+    fn plan_static_records_keep_unrelated_records() {
+        let zone = vec![
+            record("1", "@", "TXT", "v=spf1 include:spf.example.net ~all"),
+            record("2", "@", "TXT", "site-verification=abc"),
+            record("3", "@", "TXT", "ip=9.9.9.9 updated=100"),
+            Record {
+                priority: Some(10),
+                ..record("4", "@", "MX", "mx1.example.com")
+            },
+            Record {
+                priority: Some(20),
+                ..record("5", "@", "MX", "MX2.example.com.")
+            },
+            record("6", "@", "A", "9.9.9.9"),
+        ];
+        let target = static_target(
+            "example.com",
+            &[
+                "example.com:TXT=ip={ipv4} updated={timestamp}",
+                "example.com:TXT=v=spf1 include:spf.example.net ~all",
+                "example.com:MX=10 mx1.example.com",
+                "example.com:MX=30 mx2.example.com",
+                "example.com:MX=40 mx3.example.com",
+            ],
+        );
+        let opt = SyncOptions {
+            prune_types: true,
+            ..opt()
+        };
+
+        let plan = super::plan(&ips(), &target, &zone, &opt, TIMESTAMP);
+        assert_eq!(
+            summary(&plan),
+            vec![
+                (Action::Update, "3", "ip=1.2.3.4 updated=1700000000"),
+                (Action::Update, "5", "mx2.example.com"),
+                (Action::Create, "", "mx3.example.com"),
+            ]
+        );
+        assert_eq!(plan.changes[1].priority, Some(30));
+        assert_eq!(plan.changes[2].priority, Some(40));
+        assert!(plan.errors.is_empty());
+    }
+
+    #[test]
+    fn plan_static_records_owned_only_ignores_unrelated_records() {
+        let zone = vec![
+            record("1", "@", "TXT", "v=spf1 ~all"),
+            record("2", "@", "TXT", "ip=9.9.9.9"),
+        ];
+        let opt = SyncOptions {
+            remark: Some("ddns".to_string()),
+            owned_only: true,
+            ..opt()
+        };
+        let target = static_target("example.com", &["example.com:TXT=hello"]);
+        let plan = super::plan(&ips(), &target, &zone, &opt, TIMESTAMP);
+        assert_eq!(summary(&plan), vec![(Action::Create, "", "hello")]);
+        assert!(plan.errors.is_empty());
+
+        // 不带备注标记的记录与模板匹配时作为冲突报告
+        let target = static_target("example.com", &["example.com:TXT=ip={ipv4}"]);
+        let plan = super::plan(&ips(), &target, &zone, &opt, TIMESTAMP);
+        assert!(plan.changes.is_empty());
+        assert_eq!(plan.errors.len(), 1);
+    }
+
+    #[test]
+    fn plan_static_only_target_is_not_pruned() {
+        let zone = vec![
+            record("1", "api", "A", "9.9.9.9"),
+            record("2", "api", "CNAME", "old.example.com"),
+        ];
+        let target = static_target(
+            "api.example.com",
+            &["api.example.com:CNAME=www.example.com"],
+        );
+        let opt = SyncOptions {
+            prune_types: true,
+            ..opt()
+        };
+        let plan = super::plan(&ips(), &target, &zone, &opt, TIMESTAMP);
+        assert_eq!(
+            summary(&plan),
+            vec![(Action::Update, "2", "www.example.com")]
+        );
+
+        // 通过 --domain 声明的域名删除未配置的类型
+        let zone = vec![
+            record("1", "www", "A", "1.2.3.4"),
+            record("2", "www", "AAAA", "2001:db8::1"),
+        ];
+        let target = domain("www.example.com:A");
+        let plan = super::plan(&ips(), &target, &zone, &opt, TIMESTAMP);
+        assert_eq!(summary(&plan), vec![(Action::Delete, "2", "")]);
+    }
+
+    #[test]
+    fn static_record_matches_template() {
+        let txt = StaticRecord::parse("a.example.com:TXT=ip={ipv4} at {timestamp}")
+            .unwrap()
+            .1;
+        assert!(txt.matches("ip=9.9.9.9 at 100"));
+        assert!(txt.matches("\"ip=9.9.9.9 at 100\""));
+        assert!(!txt.matches("ip=9.9.9.9 at"));
+        assert!(!txt.matches("ip=host at 100"));
+        assert!(!txt.matches("v=spf1 ~all"));
+
+        let v6 = StaticRecord::parse("a.example.com:TXT={ipv6}").unwrap().1;
+        assert!(v6.matches("2001:db8::1"));
+        assert!(!v6.matches("v=spf1"));
+
+        let mx = StaticRecord::parse("example.com:MX=10 Mail.example.com.")
+            .unwrap()
+            .1;
+        assert!(mx.matches("mail.EXAMPLE.com."));
+        assert!(!mx.matches("mx.example.com"));
+    }
+
+    fn split_records(records: &[&str]) -> Result<HashMap<String, Vec<Target>>, CommonError> {
+        let mut options = Options::new(Mode::Cli);
+        options.records = records.iter().map(|r| r.to_string()).collect();
+        split(&options)
+    }
+
+    #[test]
+    fn split_allows_several_static_records_per_type() {
+        let targets = split_records(&[
+            "example.com:MX=10 mx1.example.com",
+            "example.com:MX=20 mx2.example.com",
+            "example.com:TXT=v=spf1 ~all",
+            "example.com:TXT=ip={ipv4}",
+        ])
+        .unwrap();
+        assert_eq!(targets["example.com"].len(), 1);
+        assert_eq!(targets["example.com"][0].records.len(), 4);
+
+        assert!(split_records(&[
+            "example.com:MX=10 mx1.example.com",
+            "example.com:MX=20 mx1.example.com",
+        ])
+        .is_err());
+        assert!(split_records(&[
+            "api.example.com:CNAME=www.example.com",
+            "api.example.com:CNAME=cdn.example.com",
+        ])
+        .is_err());
+    }
 }
@@ -5,14 +5,55 @@ pub fn init<'a>() -> ArgMatches<'a> {
         (version: super::VERSION)
         (author: super::AUTHORS)
         (about: "阿里云DDNS动态域名工具")
+        (@setting SubcommandsNegateReqs)
         (@arg VERBOSE: -v "设置日志等级")
         (@arg MODE: -m --mode +takes_value default_value("cli") possible_values(&["cli", "env"]) "运行模式")
 
-        (@arg (super::OPTION_AKID) : -i --akid +takes_value required_if("MODE", "cli") requires[AKSCT DOMAIN] "阿里云 Access Key ID")
-        (@arg (super::OPTION_AKSCT) : -s --aksct +takes_value requires[AKID DOMAIN] "阿里云 Access Key Secret")
-        (@arg (super::OPTION_DOMAIN) : -d --domain +takes_value requires[AKID AKSCT] "需要更新的域名，如多个域名需使用 “,” 分隔")
+        (@arg (super::OPTION_AKID) : -i --akid +takes_value requires[AKSCT] conflicts_with[RAM_ROLE] "阿里云 Access Key ID")
+        (@arg (super::OPTION_AKSCT) : -s --aksct +takes_value requires[AKID] "阿里云 Access Key Secret")
+        (@arg (super::OPTION_TOKEN) : --token +takes_value requires[AKID AKSCT] "阿里云 STS Security Token")
+        (@arg (super::OPTION_RAM_ROLE) : --("ram-role") +takes_value "ECS实例RAM角色名称，使用实例元数据中的临时凭证代替AccessKey")
+        (@arg (super::OPTION_METADATA_URL) : --("metadata-url") +takes_value requires[RAM_ROLE] "ECS实例元数据服务地址")
+        (@arg (super::OPTION_ROLE_ARN) : --("role-arn") +takes_value "需要扮演的RAM角色ARN，使用基础凭证调用STS AssumeRole获取临时凭证")
+        (@arg (super::OPTION_ROLE_SESSION_NAME) : --("role-session-name") +takes_value requires[ROLE_ARN] "扮演RAM角色时的会话名称")
+        (@arg (super::OPTION_ENDPOINT) : --endpoint +takes_value "阿里云DNS API接入点，如: alidns.ap-southeast-1.aliyuncs.com")
+        (@arg (super::OPTION_STS_ENDPOINT) : --("sts-endpoint") +takes_value requires[ROLE_ARN] "阿里云STS API接入点")
+        (@arg (super::OPTION_REGION) : --region +takes_value default_value("cn-hangzhou") "阿里云地域ID")
+        (@arg (super::OPTION_SIGNATURE_VERSION) : --("signature-version") +takes_value default_value("v1") possible_values(&["v1", "v3"]) "阿里云API签名版本，v1: HMAC-SHA1，v3: ACS3-HMAC-SHA256")
+        (@arg (super::OPTION_HTTP_METHOD) : --("http-method") +takes_value default_value("get") possible_values(&["get", "post"]) "阿里云API请求方式，post: 参数通过表单请求体传递")
+        (@arg (super::OPTION_POST_ACTIONS) : --("post-actions") +takes_value "始终使用POST请求的Action，如多个需使用 “,” 分隔")
+        (@arg (super::OPTION_CONNECT_TIMEOUT) : --("connect-timeout") +takes_value default_value("10") "HTTP连接超时时间(秒)")
+        (@arg (super::OPTION_TIMEOUT) : --timeout +takes_value default_value("30") "HTTP请求超时时间(秒)，包含读取响应的时间")
+        (@arg (super::OPTION_PROXY) : --proxy +takes_value "阿里云API请求代理，如: http://proxy:8080、socks5://proxy:1080")
+        (@arg (super::OPTION_IP_PROXY) : --("ip-proxy") +takes_value "公网IP检测请求代理")
+        (@arg (super::OPTION_CA_CERT) : --("ca-cert") +takes_value "额外信任的根证书(PEM)文件路径，如多个需使用 “,” 分隔")
+        (@arg (super::OPTION_DOMAIN) : -d --domain +takes_value required_if("MODE", "cli") "需要更新的域名，如多个域名需使用 “,” 分隔")
+        (@arg (super::OPTION_RECORDS) : --record +takes_value +multiple number_of_values(1) "静态或模板解析记录，格式: “域名:类型=解析值”，支持CNAME、TXT、MX、SRV，解析值可引用 {ipv4}、{ipv6}、{timestamp}；多条记录需重复指定")
         (@arg (super::OPTION_PERIOD) : -p --period +takes_value default_value("600") "域名解析更新时间，建议与TTL值一致")
         (@arg (super::OPTION_TTL) : -t --ttl +takes_value default_value("600") "域名解析TTL值")
+        (@arg (super::OPTION_RECORD_TYPES) : --("record-types") +takes_value "默认维护的解析类型，可选A、AAAA，如多个需使用 “,” 分隔；单个域名可通过 “域名:A+AAAA” 指定；未配置时维护A解析及已存在的AAAA解析")
+        (@arg (super::OPTION_PRUNE_TYPES) : --("prune-types") "删除域名下未配置的A/AAAA解析记录")
+        (@arg (super::OPTION_STALE) : --stale +takes_value default_value("keep") possible_values(&["keep", "disable", "delete"]) "未获取到本地对应类型IP地址时解析记录的处理方式；单个域名可通过 “域名?stale=delete” 指定")
+        (@arg (super::OPTION_LINE) : --line +takes_value default_value("default") "解析线路，如: default、telecom、unicom、mobile、oversea；单个域名可通过 “域名?line=telecom” 指定")
+        (@arg (super::OPTION_MEMBER) : --member +takes_value "成员标识，仅维护备注为该标识的解析记录，并通过权重与其他实例共同解析同一域名；单个域名可通过 “域名?member=node1” 指定")
+        (@arg (super::OPTION_WEIGHT) : --weight +takes_value default_value("1") "成员模式下的解析权重(1-100)；单个域名可通过 “域名?weight=10” 指定")
+        (@arg (super::OPTION_DUPLICATES) : --duplicates +takes_value default_value("refuse") possible_values(&["delete", "disable", "refuse"]) "同一类型存在多条解析记录时的处理方式，delete: 更新一条并删除其余，disable: 更新一条并暂停其余，refuse: 不做修改并报告")
+        (@arg (super::OPTION_EXTERNAL_RECORDS) : --("external-records") +takes_value "外部维护的解析记录ID或解析值，不做任何修改，如多个需使用 “,” 分隔")
+        (@arg (super::OPTION_REMARK) : --remark +takes_value "备注标记，创建的解析记录会设置该备注")
+        (@arg (super::OPTION_OWNED_ONLY) : --("owned-only") requires[REMARK] "仅修改带有备注标记的解析记录，其他记录作为冲突报告")
+        (@arg (super::OPTION_DISABLED) : --disabled +takes_value default_value("on-change") possible_values(&["keep", "on-change", "always"]) "暂停状态解析记录的处理方式，keep: 保持暂停，on-change: 解析值变化时启用，always: 始终启用")
+        (@arg (super::OPTION_CONCURRENCY) : --concurrency +takes_value default_value("4") "同时处理的域名数量上限")
+        (@arg (super::OPTION_QPS) : --qps +takes_value default_value("10") "阿里云API每秒请求数上限，0为不限制")
+        (@arg (super::OPTION_DRY_RUN) : --("dry-run") "仅执行一次检测与解析记录查询，输出计划创建、更新、启用、暂停与删除的解析记录，不修改任何解析记录")
+        (@arg (super::OPTION_PLAN_FORMAT) : --("plan-format") +takes_value default_value("human") possible_values(&["human", "json"]) "同步计划的输出格式")
+
+        (@subcommand acme =>
+            (about: "ACME DNS-01 验证，创建或删除 _acme-challenge TXT 解析记录；兼容 certbot 的 --manual-auth-hook/--manual-cleanup-hook 与 lego 的 exec 方式")
+            (@arg (super::OPTION_ACME_ACTION) : +required possible_values(&["auth", "present", "cleanup"]) "auth/present: 创建验证记录，cleanup: 删除验证记录")
+            (@arg (super::OPTION_ACME_DOMAIN) : "待验证域名或验证记录域名，未指定时读取环境变量 CERTBOT_DOMAIN")
+            (@arg (super::OPTION_ACME_VALUE) : "验证值，未指定时读取环境变量 CERTBOT_VALIDATION")
+            (@arg (super::OPTION_ACME_WAIT) : --wait +takes_value default_value("0") "创建验证记录后等待生效的时间(秒)")
+        )
     );
 
     app.get_matches()
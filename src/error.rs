@@ -1,4 +1,100 @@
 use std::error::Error;
+use url::Url;
 
 /// 公共错误处理
-pub type CommonError = Box<dyn Error>;
+pub type CommonError = Box<dyn Error + Send + Sync>;
+
+/// 日志中需要隐藏的URL参数
+const SENSITIVE_PARAMS: &[&str] = &["Signature", "AccessKeyId", "SecurityToken"];
+
+/// URL参数脱敏，隐藏签名与凭证信息
+pub fn redact_url(url: &Url) -> Url {
+    let mut redacted = url.clone();
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| {
+            if SENSITIVE_PARAMS.contains(&k.as_ref()) {
+                (k.to_string(), "******".to_string())
+            } else {
+                (k.to_string(), v.to_string())
+            }
+        })
+        .collect();
+    if !pairs.is_empty() {
+        redacted.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    redacted
+}
+
+/// 错误信息脱敏，隐藏其中携带的签名与凭证参数
+/// 如 SignatureDoesNotMatch 错误信息中服务端的待签名字符串: ...&AccessKeyId%3DLTAI...%26Action%3D...
+pub fn redact_message(message: &str) -> String {
+    let mut redacted = message.to_string();
+    for param in SENSITIVE_PARAMS {
+        // 原始参数与待签名字符串中编码后的参数
+        for (assign, end) in &[("=", "&"), ("%3D", "%26")] {
+            redacted = redact_param(&redacted, &format!("{}{}", param, assign), end);
+        }
+    }
+    redacted
+}
+
+/// 替换 key 之后直到 end 或空白字符之前的参数值
+fn redact_param(message: &str, key: &str, end: &str) -> String {
+    let mut redacted = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(i) = rest.find(key) {
+        let start = i + key.len();
+        redacted.push_str(&rest[..start]);
+        redacted.push_str("******");
+        let value = &rest[start..];
+        let len = value.find(char::is_whitespace).unwrap_or(value.len());
+        let len = value[..len].find(end).unwrap_or(len);
+        rest = &value[len..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// 转换请求错误，移除错误信息中URL携带的签名与凭证
+pub fn redact_request_error(err: reqwest::Error) -> CommonError {
+    let message = match err.url() {
+        Some(url) => err
+            .to_string()
+            .replace(url.as_str(), redact_url(url).as_str()),
+        None => err.to_string(),
+    };
+    message.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_url_hides_credentials() {
+        let url = Url::parse("https://alidns.aliyuncs.com/?Action=DescribeDomains&AccessKeyId=LTAI_ID&Signature=abc%2Fdef&SecurityToken=STS_TOKEN").unwrap();
+        let redacted = redact_url(&url).to_string();
+        assert!(redacted.contains("Action=DescribeDomains"));
+        assert!(!redacted.contains("LTAI_ID"));
+        assert!(!redacted.contains("abc"));
+        assert!(!redacted.contains("STS_TOKEN"));
+        assert!(!format!("{:?}", redact_url(&url)).contains("LTAI_ID"));
+    }
+
+    #[test]
+    fn redact_message_hides_string_to_sign() {
+        let message = "Specified signature is not matched with our calculation. server string to sign is:GET&%2F&AccessKeyId%3DLTAI_ID%26Action%3DDescribeDomains%26SecurityToken%3DSTS%252BTOKEN%26SignatureMethod%3DHMAC-SHA1";
+        let redacted = redact_message(message);
+        assert!(!redacted.contains("LTAI_ID"));
+        assert!(!redacted.contains("STS%252BTOKEN"));
+        assert!(redacted.contains("AccessKeyId%3D******%26Action%3DDescribeDomains"));
+        assert!(redacted.contains("SignatureMethod%3DHMAC-SHA1"));
+
+        let redacted = redact_message("invalid AccessKeyId=LTAI_ID&Signature=abc other");
+        assert_eq!(
+            redacted,
+            "invalid AccessKeyId=******&Signature=****** other"
+        );
+    }
+}
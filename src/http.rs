@@ -0,0 +1,56 @@
+use crate::error::CommonError;
+use crate::secret::Secret;
+use fehler::throws;
+use reqwest::{Certificate, Client, Proxy};
+use std::fs;
+use std::time::Duration;
+
+/// 默认连接超时时间(秒)
+pub const CONNECT_TIMEOUT_SECS: u64 = 10;
+/// 默认请求超时时间(秒)，包含读取响应的时间
+pub const TIMEOUT_SECS: u64 = 30;
+
+/// 出站HTTP请求配置
+#[derive(Clone, Debug)]
+pub struct HttpOptions {
+    pub connect_timeout: u64,
+    pub timeout: u64,
+    /// 代理地址，如: http://proxy:8080、socks5://proxy:1080(需启用socks特性)
+    pub proxy: Option<Secret>,
+    /// 额外信任的根证书(PEM)文件路径
+    pub ca_certs: Vec<String>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        HttpOptions {
+            connect_timeout: CONNECT_TIMEOUT_SECS,
+            timeout: TIMEOUT_SECS,
+            proxy: None,
+            ca_certs: Vec::new(),
+        }
+    }
+}
+
+impl HttpOptions {
+    /// 构建HTTP客户端
+    #[throws(CommonError)]
+    pub fn build_client(&self) -> Client {
+        // 每次请求均在独立的Runtime中执行，禁用连接池避免复用已失效的连接
+        let mut builder = Client::builder()
+            .connect_timeout(Duration::from_secs(self.connect_timeout))
+            .timeout(Duration::from_secs(self.timeout))
+            .pool_max_idle_per_host(0);
+        if let Some(proxy) = self.proxy.as_ref() {
+            let proxy = Proxy::all(proxy.expose()).map_err(|_| "代理地址配置错误")?;
+            builder = builder.proxy(proxy);
+        }
+        for path in self.ca_certs.iter() {
+            let pem = fs::read(path).map_err(|e| format!("读取证书文件{}失败: {}", path, e))?;
+            let cert = Certificate::from_pem(&pem)
+                .map_err(|e| format!("解析证书文件{}失败: {}", path, e))?;
+            builder = builder.add_root_certificate(cert);
+        }
+        builder.build()?
+    }
+}
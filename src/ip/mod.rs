@@ -1,6 +1,6 @@
 use crate::error::CommonError;
 use fehler::throws;
-use reqwest;
+use reqwest::Client;
 use std::net::{Ipv4Addr, Ipv6Addr};
 use tokio::runtime::Runtime;
 
@@ -11,6 +11,20 @@ pub enum IPOption {
 }
 
 impl IPOption {
+    pub fn ipv4(&self) -> Ipv4Addr {
+        match *self {
+            IPOption::IPV4(v4) => v4,
+            IPOption::IPAll(v4, _) => v4,
+        }
+    }
+
+    pub fn ipv6(&self) -> Option<Ipv6Addr> {
+        match *self {
+            IPOption::IPV4(_) => None,
+            IPOption::IPAll(_, v6) => Some(v6),
+        }
+    }
+
     #[throws(CommonError)]
     fn combine_ipv4(ipv4_str: String) -> IPOption {
         IPOption::IPV4(ipv4_str.parse::<Ipv4Addr>()?)
@@ -22,7 +36,7 @@ impl IPOption {
             match res {
                 Ok(ipv6_str) => IPOption::IPAll(v4, ipv6_str.parse::<Ipv6Addr>()?),
                 Err(e) => {
-                    println!("request ipv6 address error: {:?}", e);
+                    log::warn!("获取IPv6地址失败: {}", e);
                     IPOption::IPV4(v4)
                 }
             }
@@ -33,17 +47,17 @@ impl IPOption {
 }
 
 #[throws(CommonError)]
-async fn get_ip(url: &str) -> String {
-    let data = reqwest::get(url).await?.text().await?;
-    let infos = data.split(",").collect::<Vec<&str>>();
+async fn get_ip(client: &Client, url: &str) -> String {
+    let data = client.get(url).send().await?.text().await?;
+    let infos = data.split(',').collect::<Vec<&str>>();
     infos[1].to_string()
 }
 
 #[throws(CommonError)]
-pub fn get_ips() -> IPOption {
+pub fn get_ips(client: &Client) -> IPOption {
     Runtime::new().unwrap().block_on(async {
-        let ipv4 = get_ip("http://ip4.me/api/");
-        let ipv6 = get_ip("http://ip6only.me/api/");
+        let ipv4 = get_ip(client, "http://ip4.me/api/");
+        let ipv6 = get_ip(client, "http://ip6only.me/api/");
         let (rv4, rv6) = tokio::join!(ipv4, ipv6);
         IPOption::combine_ipv4(rv4?)?.combine_ipv6(rv6)
     })?
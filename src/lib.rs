@@ -1,9 +1,12 @@
+pub mod acme;
 pub mod aliyun;
 pub mod argument;
 pub mod config;
 pub mod error;
+pub mod http;
 pub mod ip;
 pub mod logger;
+pub mod secret;
 pub mod task;
 
 use self::{config::Options, task::DomainUpdate};
@@ -16,9 +19,50 @@ pub const AUTHORS: &str = env!("CARGO_PKG_AUTHORS");
 /// option
 pub const OPTION_AKID: &str = "AKID";
 pub const OPTION_AKSCT: &str = "AKSCT";
+pub const OPTION_TOKEN: &str = "AKTOKEN";
+pub const OPTION_RAM_ROLE: &str = "RAM_ROLE";
+pub const OPTION_METADATA_URL: &str = "METADATA_URL";
+pub const OPTION_ROLE_ARN: &str = "ROLE_ARN";
+pub const OPTION_ROLE_SESSION_NAME: &str = "ROLE_SESSION_NAME";
+pub const OPTION_ENDPOINT: &str = "ENDPOINT";
+pub const OPTION_STS_ENDPOINT: &str = "STS_ENDPOINT";
+pub const OPTION_REGION: &str = "REGION_ID";
+pub const OPTION_SIGNATURE_VERSION: &str = "SIGNATURE_VERSION";
+pub const OPTION_HTTP_METHOD: &str = "HTTP_METHOD";
+pub const OPTION_POST_ACTIONS: &str = "POST_ACTIONS";
+pub const OPTION_CONNECT_TIMEOUT: &str = "CONNECT_TIMEOUT";
+pub const OPTION_TIMEOUT: &str = "TIMEOUT";
+pub const OPTION_PROXY: &str = "PROXY";
+pub const OPTION_IP_PROXY: &str = "IP_PROXY";
+pub const OPTION_CA_CERT: &str = "CA_CERT";
 pub const OPTION_DOMAIN: &str = "DOMAIN";
+pub const OPTION_RECORDS: &str = "RECORDS";
 pub const OPTION_PERIOD: &str = "PERIOD";
 pub const OPTION_TTL: &str = "TTL";
+pub const OPTION_CONCURRENCY: &str = "CONCURRENCY";
+pub const OPTION_QPS: &str = "QPS";
+pub const OPTION_RECORD_TYPES: &str = "RECORD_TYPES";
+pub const OPTION_PRUNE_TYPES: &str = "PRUNE_TYPES";
+pub const OPTION_STALE: &str = "STALE";
+pub const OPTION_LINE: &str = "LINE";
+pub const OPTION_MEMBER: &str = "MEMBER";
+pub const OPTION_WEIGHT: &str = "WEIGHT";
+pub const OPTION_DUPLICATES: &str = "DUPLICATES";
+pub const OPTION_EXTERNAL_RECORDS: &str = "EXTERNAL_RECORDS";
+pub const OPTION_REMARK: &str = "REMARK";
+pub const OPTION_OWNED_ONLY: &str = "OWNED_ONLY";
+pub const OPTION_DISABLED: &str = "DISABLED";
+pub const OPTION_DRY_RUN: &str = "DRY_RUN";
+pub const OPTION_PLAN_FORMAT: &str = "PLAN_FORMAT";
+
+/// acme subcommand
+pub const COMMAND_ACME: &str = "acme";
+pub const OPTION_ACME_ACTION: &str = "ACME_ACTION";
+pub const OPTION_ACME_DOMAIN: &str = "ACME_DOMAIN";
+pub const OPTION_ACME_VALUE: &str = "ACME_VALUE";
+pub const OPTION_ACME_WAIT: &str = "ACME_WAIT";
+/// 从文件中读取敏感配置的环境变量后缀，如: AKSCT_FILE
+pub const OPTION_FILE_SUFFIX: &str = "_FILE";
 
 pub fn start(options: Options) {
     log::info!("version: {}", VERSION);
@@ -1,10 +1,17 @@
 use simplelog::*;
 
 /// init logger
-pub fn init() {
-    let term_logger = TermLogger::new(LevelFilter::Info, Config::default(), TerminalMode::Mixed);
+/// stderr 为 true 时日志全部输出到 stderr，避免与 stdout 输出的同步计划混在一起
+pub fn init(stderr: bool) {
+    let mode = if stderr {
+        TerminalMode::Stderr
+    } else {
+        TerminalMode::Mixed
+    };
+    let term_logger = TermLogger::new(LevelFilter::Info, Config::default(), mode);
     let logger: Box<dyn SharedLogger> = match term_logger {
         Some(var) => var,
+        None if stderr => WriteLogger::new(LevelFilter::Info, Config::default(), std::io::stderr()),
         None => SimpleLogger::new(LevelFilter::Info, Config::default()),
     };
 
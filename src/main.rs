@@ -1,13 +1,28 @@
-use aliyunddns::{argument, config::Mode, config::Options, logger};
+use aliyunddns::{acme::Challenge, argument, config::Mode, config::Options, logger};
 use clap::value_t;
+use std::process;
 
 fn main() {
     let args = argument::init();
     let options = match value_t!(args, "MODE", Mode).unwrap() {
-        Mode::Cli => Options::from_args(&args),
-        Mode::Env => Options::from_env(),
+        Mode::Cli => {
+            Options::from_args(&args).map_err(|e| format!("读取命令行参数配置失败: {}", e))
+        }
+        Mode::Env => Options::from_env().map_err(|e| format!("读取环境变量配置失败: {}", e)),
     };
+    let options = options.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
 
-    logger::init();
-    aliyunddns::start(options);
+    logger::init(options.dry_run);
+    match args.subcommand_matches(aliyunddns::COMMAND_ACME) {
+        Some(args) => Challenge::from_args(args)
+            .unwrap_or_else(|e| {
+                log::error!("ACME验证参数错误: {}", e);
+                process::exit(1);
+            })
+            .run(&options),
+        None => aliyunddns::start(options),
+    }
 }
@@ -1,7 +1,16 @@
-use crate::aliyun;
+use crate::aliyun::record::{self, Plan, PlanFormat, Record, SyncOutcome, Target};
+use crate::aliyun::{self, AliDnsClient};
 use crate::config::Options;
-use crate::ip;
-use std::{thread, time::Duration, time::Instant};
+use crate::ip::{self, IPOption};
+use std::collections::HashMap;
+use std::process;
+use std::sync::Mutex;
+use std::{thread, time::Duration, time::Instant, time::SystemTime, time::UNIX_EPOCH};
+
+/// 单个域名的同步结果，失败时为错误信息
+type SyncResult = Result<SyncOutcome, String>;
+/// 主域名下的目标域名及其解析记录，拉取失败时为错误信息
+type Zone = (Vec<Target>, Result<Vec<Record>, String>);
 
 pub struct DomainUpdate {
     options: Options,
@@ -9,13 +18,38 @@ pub struct DomainUpdate {
 
 impl DomainUpdate {
     pub fn new(options: Options) -> Self {
-        DomainUpdate { options: options }
+        DomainUpdate { options }
     }
 
     pub fn run(&self) {
         let options = &self.options;
-        if options.domains.len() == 0 {
-            panic!("缺少域名信息");
+        if options.domains.is_empty() {
+            log::error!("缺少域名信息");
+            process::exit(1);
+        }
+        let client = options.client().unwrap_or_else(|e| {
+            log::error!("阿里云DNS客户端配置错误: {}", e);
+            process::exit(1);
+        });
+        let ip_client = options.ip_client().unwrap_or_else(|e| {
+            log::error!("公网IP检测HTTP客户端配置错误: {}", e);
+            process::exit(1);
+        });
+
+        if options.dry_run {
+            let result = aliyun::split_records(options).and_then(|records| {
+                let ips = ip::get_ips(&ip_client)?;
+                log::info!("本地公网IP信息:{:?}", ips);
+                Ok(plan(&client, &ips, records, options))
+            });
+            match result {
+                Ok(plans) => print_plans(&plans, options.plan_format),
+                Err(e) => {
+                    log::error!("生成同步计划失败, error:{:?}", e);
+                    process::exit(1);
+                }
+            }
+            return;
         }
 
         let period_duration = Duration::from_secs(options.period as u64);
@@ -23,19 +57,17 @@ impl DomainUpdate {
         loop {
             let start = Instant::now();
             let result = aliyun::split_records(options).and_then(|records| {
-                aliyun::list_records(options, &records).and_then(|(update_rs, create_rs)| {
-                    let _x = ip::get_ips().and_then(|ips| {
-                        log::info!("本地公网IP信息:{:?}", ips);
-                        if update_rs.len() > 0 {
-                            aliyun::update_records(options, &ips, &update_rs)?;
-                        }
-                        if create_rs.len() > 0 {
-                            aliyun::create_records(options, &ips, &create_rs)?;
-                        }
-                        Ok(())
-                    })?;
-                    Ok(())
-                })
+                let ips = ip::get_ips(&ip_client)?;
+                log::info!("本地公网IP信息:{:?}", ips);
+                let summary = reconcile(&client, &ips, records, options);
+                log::info!("本轮同步结果:");
+                for (name, result) in summary {
+                    match result {
+                        Ok(outcome) => log::info!("{}: {}", name, outcome),
+                        Err(e) => log::error!("{}: 失败, {}", name, e),
+                    }
+                }
+                Ok(())
             });
             if let Err(e) = result {
                 log::error!("阿里云端处理失败, error:{:?}", e);
@@ -49,3 +81,133 @@ impl DomainUpdate {
         }
     }
 }
+
+/// 并发同步所有域名，单个域名失败不影响其他域名
+/// 先按主域名并发拉取解析记录，再按域名并发更新或创建
+fn reconcile(
+    client: &AliDnsClient,
+    ips: &IPOption,
+    targets: HashMap<String, Vec<Target>>,
+    options: &Options,
+) -> Vec<(String, SyncResult)> {
+    let limit = options.concurrency;
+    let sync_options = options.sync_options();
+    let timestamp = timestamp();
+    let zones = fetch_zones(client, targets, limit);
+
+    let mut summary = Vec::new();
+    let mut jobs: Vec<(&Target, &[Record])> = Vec::new();
+    for (targets, zone) in zones.iter() {
+        match zone {
+            Ok(zone) => jobs.extend(targets.iter().map(|t| (t, &zone[..]))),
+            Err(e) => summary.extend(targets.iter().map(|t| (t.to_string(), Err(e.clone())))),
+        }
+    }
+
+    summary.extend(concurrent(jobs, limit, |(target, zone)| {
+        let result = record::sync(client, ips, target, zone, &sync_options, timestamp)
+            .map_err(|e| e.to_string());
+        (target.to_string(), result)
+    }));
+    summary
+}
+
+/// 生成所有域名的同步计划，不修改任何解析记录，按域名排序
+fn plan(
+    client: &AliDnsClient,
+    ips: &IPOption,
+    targets: HashMap<String, Vec<Target>>,
+    options: &Options,
+) -> Vec<Plan> {
+    let sync_options = options.sync_options();
+    let timestamp = timestamp();
+    let mut plans = Vec::new();
+    for (targets, zone) in fetch_zones(client, targets, options.concurrency) {
+        for target in targets.iter() {
+            plans.push(match zone.as_ref() {
+                Ok(zone) => record::plan(ips, target, zone, &sync_options, timestamp),
+                Err(e) => Plan {
+                    name: target.to_string(),
+                    errors: vec![e.clone()],
+                    ..Default::default()
+                },
+            });
+        }
+    }
+    plans.sort_by(|a, b| a.name.cmp(&b.name));
+    plans
+}
+
+/// 输出同步计划
+fn print_plans(plans: &[Plan], format: PlanFormat) {
+    match format {
+        PlanFormat::Json => match serde_json::to_string_pretty(plans) {
+            Ok(json) => println!("{}", json),
+            Err(e) => log::error!("序列化同步计划失败, error:{:?}", e),
+        },
+        PlanFormat::Human => {
+            for plan in plans {
+                println!("{}:", plan.name);
+                if plan.changes.is_empty() && plan.errors.is_empty() {
+                    println!("  {}", SyncOutcome::Unchanged);
+                }
+                for change in plan.changes.iter() {
+                    println!("  {}", change);
+                }
+                for e in plan.errors.iter() {
+                    println!("  错误: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 本轮同步的Unix时间戳(秒)，用于渲染解析值模板
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// 按主域名并发拉取解析记录
+fn fetch_zones(
+    client: &AliDnsClient,
+    targets: HashMap<String, Vec<Target>>,
+    limit: usize,
+) -> Vec<Zone> {
+    concurrent(targets.into_iter().collect(), limit, |(domain, targets)| {
+        let zone = record::zone_records(client, &domain).map_err(|e| e.to_string());
+        (targets, zone)
+    })
+}
+
+/// 使用最多 limit 个线程并发处理，返回结果与输入顺序一致
+fn concurrent<T, R, F>(items: Vec<T>, limit: usize, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let workers = limit.max(1).min(items.len());
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::new());
+    thread::scope(|s| {
+        for _ in 0..workers {
+            s.spawn(|| loop {
+                let next = queue.lock().unwrap().next();
+                match next {
+                    Some((i, item)) => {
+                        let r = f(item);
+                        results.lock().unwrap().push((i, r));
+                    }
+                    None => break,
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
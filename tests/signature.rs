@@ -0,0 +1,108 @@
+use aliyunddns::aliyun::common::{canonical_query, percent_encode, rpc_signature, sign_v3};
+use aliyunddns::aliyun::credential::Credential;
+use aliyunddns::secret::Secret;
+use std::collections::BTreeMap;
+
+/// 阿里云RPC签名文档中的示例请求(DescribeRegions)
+#[test]
+fn rpc_signature_documented_example() {
+    let params: BTreeMap<String, String> = vec![
+        ("Timestamp", "2016-02-23T12:46:24Z"),
+        ("Format", "XML"),
+        ("AccessKeyId", "testid"),
+        ("Action", "DescribeRegions"),
+        ("SignatureMethod", "HMAC-SHA1"),
+        ("SignatureNonce", "3ee8c1b8-83d3-44af-a94f-4e0ad82fd6cf"),
+        ("Version", "2014-05-26"),
+        ("SignatureVersion", "1.0"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    assert_eq!(
+        canonical_query(&params),
+        "AccessKeyId=testid&Action=DescribeRegions&Format=XML&SignatureMethod=HMAC-SHA1\
+         &SignatureNonce=3ee8c1b8-83d3-44af-a94f-4e0ad82fd6cf&SignatureVersion=1.0\
+         &Timestamp=2016-02-23T12%3A46%3A24Z&Version=2014-05-26"
+    );
+    assert_eq!(
+        rpc_signature("GET", &params, "testsecret"),
+        "OLeaidS1JvxuMvnyHOwuJ+uX5qY="
+    );
+}
+
+#[test]
+fn percent_encode_rfc3986() {
+    assert_eq!(percent_encode("AZaz09-_.~"), "AZaz09-_.~");
+    assert_eq!(
+        percent_encode("v=spf1 include:*.example.com ~all"),
+        "v%3Dspf1%20include%3A%2A.example.com%20~all"
+    );
+    assert_eq!(percent_encode("备注 ü"), "%E5%A4%87%E6%B3%A8%20%C3%BC");
+}
+
+/// 阿里云V3签名文档中的示例请求(RunInstances)，输入与期望值均取自文档
+#[test]
+fn sign_v3_documented_example() {
+    let credential = Credential::new(
+        Secret::from("YourAccessKeyId"),
+        Secret::from("YourAccessKeySecret"),
+    );
+    let mut params = BTreeMap::new();
+    params.insert(
+        "ImageId".to_string(),
+        "win2019_1809_x64_dtc_zh-cn_40G_alibase_20230811.vhd".to_string(),
+    );
+    params.insert("RegionId".to_string(), "cn-shanghai".to_string());
+
+    let mut headers = BTreeMap::new();
+    headers.insert("x-acs-action".to_string(), "RunInstances".to_string());
+    headers.insert("x-acs-version".to_string(), "2014-05-26".to_string());
+    headers.insert("x-acs-date".to_string(), "2023-10-26T10:22:32Z".to_string());
+    headers.insert(
+        "x-acs-signature-nonce".to_string(),
+        "3156853299f313e23d1673dc12e1703d".to_string(),
+    );
+
+    let headers = sign_v3(
+        "POST",
+        "ecs.cn-shanghai.aliyuncs.com",
+        "/",
+        &params,
+        b"",
+        headers,
+        &credential,
+    );
+
+    assert_eq!(
+        headers["x-acs-content-sha256"],
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        headers["Authorization"],
+        "ACS3-HMAC-SHA256 Credential=YourAccessKeyId,\
+         SignedHeaders=host;x-acs-action;x-acs-content-sha256;x-acs-date;x-acs-signature-nonce;x-acs-version,\
+         Signature=06563a9e1b43f5dfe96b81484da74bceab24a1d853912eee15083a6f0f3283c0"
+    );
+}
+
+#[test]
+fn sign_v3_security_token_is_signed() {
+    let mut credential = Credential::new(Secret::from("STS.id"), Secret::from("secret"));
+    credential.security_token = Some(Secret::from("token"));
+
+    let headers = sign_v3(
+        "GET",
+        "alidns.aliyuncs.com",
+        "/",
+        &BTreeMap::new(),
+        b"",
+        BTreeMap::new(),
+        &credential,
+    );
+
+    assert_eq!(headers["x-acs-security-token"], "token");
+    assert!(headers["Authorization"]
+        .contains("SignedHeaders=host;x-acs-content-sha256;x-acs-security-token,"));
+}